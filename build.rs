@@ -3,15 +3,28 @@
 //  Licensed under MIT License, see License file for more details
 //  git clone https://github.com/marcomq/tauri-plugin-python
 
+#[path = "src/scope.rs"]
+mod scope;
+
 const COMMANDS: &[&str] = &[
     "run_python",
     "register_function",
+    "unregister_function",
     "call_function",
+    "call_function_json",
+    "call_function_stream",
+    "ack_stream",
+    "cancel_call",
     "read_variable",
+    "get_status",
+    "wait_ready",
+    "get_output",
+    "list_functions",
 ];
 
 fn main() {
     tauri_plugin::Builder::new(COMMANDS)
+        .global_scope_schema(schemars::schema_for!(scope::FunctionScope))
         .global_api_script_path("./dist-js/index.iife.js")
         .android_path("android")
         .ios_path("ios")
@@ -2,28 +2,164 @@ if ('__TAURI__' in window) {
 var __TAURI_PLUGIN_PYTHON_API__ = (function (exports) {
     'use strict';
 
-    /******************************************************************************
-    Copyright (c) Microsoft Corporation.
-
-    Permission to use, copy, modify, and/or distribute this software for any
-    purpose with or without fee is hereby granted.
-
-    THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES WITH
-    REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF MERCHANTABILITY
-    AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR ANY SPECIAL, DIRECT,
-    INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES WHATSOEVER RESULTING FROM
-    LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION OF CONTRACT, NEGLIGENCE OR
-    OTHER TORTIOUS ACTION, ARISING OUT OF OR IN CONNECTION WITH THE USE OR
-    PERFORMANCE OF THIS SOFTWARE.
-    ***************************************************************************** */
-    /* global Reflect, Promise, SuppressedError, Symbol, Iterator */
-
-
-    typeof SuppressedError === "function" ? SuppressedError : function (error, suppressed, message) {
-        var e = new Error(message);
-        return e.name = "SuppressedError", e.error = error, e.suppressed = suppressed, e;
+    /******************************************************************************
+    Copyright (c) Microsoft Corporation.
+
+    Permission to use, copy, modify, and/or distribute this software for any
+    purpose with or without fee is hereby granted.
+
+    THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES WITH
+    REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF MERCHANTABILITY
+    AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR ANY SPECIAL, DIRECT,
+    INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES WHATSOEVER RESULTING FROM
+    LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION OF CONTRACT, NEGLIGENCE OR
+    OTHER TORTIOUS ACTION, ARISING OUT OF OR IN CONNECTION WITH THE USE OR
+    PERFORMANCE OF THIS SOFTWARE.
+    ***************************************************************************** */
+    /* global Reflect, Promise, SuppressedError, Symbol, Iterator */
+
+
+    function __classPrivateFieldGet(receiver, state, kind, f) {
+        if (kind === "a" && !f) throw new TypeError("Private accessor was defined without a getter");
+        if (typeof state === "function" ? receiver !== state || !f : !state.has(receiver)) throw new TypeError("Cannot read private member from an object whose class did not declare it");
+        return kind === "m" ? f : kind === "a" ? f.call(receiver) : f ? f.value : state.get(receiver);
+    }
+
+    function __classPrivateFieldSet(receiver, state, value, kind, f) {
+        if (kind === "m") throw new TypeError("Private method is not writable");
+        if (kind === "a" && !f) throw new TypeError("Private accessor was defined without a setter");
+        if (typeof state === "function" ? receiver !== state || !f : !state.has(receiver)) throw new TypeError("Cannot write private member to an object whose class did not declare it");
+        return (kind === "a" ? f.call(receiver, value) : f ? f.value = value : state.set(receiver, value)), value;
+    }
+
+    typeof SuppressedError === "function" ? SuppressedError : function (error, suppressed, message) {
+        var e = new Error(message);
+        return e.name = "SuppressedError", e.error = error, e.suppressed = suppressed, e;
     };
 
+    var _Channel_onmessage, _Channel_nextMessageIndex, _Channel_pendingMessages, _Channel_messageEndIndex;
+    /**
+     * A key to be used to implement a special function
+     * on your types that define how your type should be serialized
+     * when passing across the IPC.
+     * @example
+     * Given a type in Rust that looks like this
+     * ```rs
+     * #[derive(serde::Serialize, serde::Deserialize)
+     * enum UserId {
+     *   String(String),
+     *   Number(u32),
+     * }
+     * ```
+     * `UserId::String("id")` would be serialized into `{ String: "id" }`
+     * and so we need to pass the same structure back to Rust
+     * ```ts
+     * import { SERIALIZE_TO_IPC_FN } from "@tauri-apps/api/core"
+     *
+     * class UserIdString {
+     *   id
+     *   constructor(id) {
+     *     this.id = id
+     *   }
+     *
+     *   [SERIALIZE_TO_IPC_FN]() {
+     *     return { String: this.id }
+     *   }
+     * }
+     *
+     * class UserIdNumber {
+     *   id
+     *   constructor(id) {
+     *     this.id = id
+     *   }
+     *
+     *   [SERIALIZE_TO_IPC_FN]() {
+     *     return { Number: this.id }
+     *   }
+     * }
+     *
+     * type UserId = UserIdString | UserIdNumber
+     * ```
+     *
+     */
+    // if this value changes, make sure to update it in:
+    // 1. ipc.js
+    // 2. process-ipc-message-fn.js
+    const SERIALIZE_TO_IPC_FN = '__TAURI_TO_IPC_KEY__';
+    /**
+     * Stores the callback in a known location, and returns an identifier that can be passed to the backend.
+     * The backend uses the identifier to `eval()` the callback.
+     *
+     * @return An unique identifier associated with the callback function.
+     *
+     * @since 1.0.0
+     */
+    function transformCallback(
+    // TODO: Make this not optional in v3
+    callback, once = false) {
+        return window.__TAURI_INTERNALS__.transformCallback(callback, once);
+    }
+    class Channel {
+        constructor(onmessage) {
+            _Channel_onmessage.set(this, void 0);
+            // the index is used as a mechanism to preserve message order
+            _Channel_nextMessageIndex.set(this, 0);
+            _Channel_pendingMessages.set(this, []);
+            _Channel_messageEndIndex.set(this, void 0);
+            __classPrivateFieldSet(this, _Channel_onmessage, onmessage || (() => { }), "f");
+            this.id = transformCallback((rawMessage) => {
+                const index = rawMessage.index;
+                if ('end' in rawMessage) {
+                    if (index == __classPrivateFieldGet(this, _Channel_nextMessageIndex, "f")) {
+                        this.cleanupCallback();
+                    }
+                    else {
+                        __classPrivateFieldSet(this, _Channel_messageEndIndex, index, "f");
+                    }
+                    return;
+                }
+                const message = rawMessage.message;
+                // Process the message if we're at the right order
+                if (index == __classPrivateFieldGet(this, _Channel_nextMessageIndex, "f")) {
+                    __classPrivateFieldGet(this, _Channel_onmessage, "f").call(this, message);
+                    __classPrivateFieldSet(this, _Channel_nextMessageIndex, __classPrivateFieldGet(this, _Channel_nextMessageIndex, "f") + 1, "f");
+                    // process pending messages
+                    while (__classPrivateFieldGet(this, _Channel_nextMessageIndex, "f") in __classPrivateFieldGet(this, _Channel_pendingMessages, "f")) {
+                        const message = __classPrivateFieldGet(this, _Channel_pendingMessages, "f")[__classPrivateFieldGet(this, _Channel_nextMessageIndex, "f")];
+                        __classPrivateFieldGet(this, _Channel_onmessage, "f").call(this, message);
+                        // eslint-disable-next-line @typescript-eslint/no-array-delete
+                        delete __classPrivateFieldGet(this, _Channel_pendingMessages, "f")[__classPrivateFieldGet(this, _Channel_nextMessageIndex, "f")];
+                        __classPrivateFieldSet(this, _Channel_nextMessageIndex, __classPrivateFieldGet(this, _Channel_nextMessageIndex, "f") + 1, "f");
+                    }
+                    if (__classPrivateFieldGet(this, _Channel_nextMessageIndex, "f") === __classPrivateFieldGet(this, _Channel_messageEndIndex, "f")) {
+                        this.cleanupCallback();
+                    }
+                }
+                // Queue the message if we're not
+                else {
+                    // eslint-disable-next-line security/detect-object-injection
+                    __classPrivateFieldGet(this, _Channel_pendingMessages, "f")[index] = message;
+                }
+            });
+        }
+        cleanupCallback() {
+            window.__TAURI_INTERNALS__.unregisterCallback(this.id);
+        }
+        set onmessage(handler) {
+            __classPrivateFieldSet(this, _Channel_onmessage, handler, "f");
+        }
+        get onmessage() {
+            return __classPrivateFieldGet(this, _Channel_onmessage, "f");
+        }
+        [(_Channel_onmessage = new WeakMap(), _Channel_nextMessageIndex = new WeakMap(), _Channel_pendingMessages = new WeakMap(), _Channel_messageEndIndex = new WeakMap(), SERIALIZE_TO_IPC_FN)]() {
+            return `__CHANNEL__:${this.id}`;
+        }
+        toJSON() {
+            // eslint-disable-next-line security/detect-object-injection
+            return this[SERIALIZE_TO_IPC_FN]();
+        }
+    }
+
     /**
      * Sends a message to the backend.
      * @example
@@ -63,21 +199,39 @@ var __TAURI_PLUGIN_PYTHON_API__ = (function (exports) {
      *  @param {string} pythonFunctionCall - The python function call, can contain one dot
      *  @param {number} [numberOfArgs] - Number of arguments, used for validation in python, use -1 to ignore this value
      *  @param {string} [jsFunctionName] - Name that is used in javascript: "call.jsFunctionName". Must not contain dots.
+     *  @param {number} [timeoutMs] - Default timeout for calls of this function, in milliseconds
      */
-    async function registerFunction(pythonFunctionCall, numberOfArgs, jsFunctionName) {
+    async function registerFunction(pythonFunctionCall, numberOfArgs, jsFunctionName, timeoutMs) {
         if (numberOfArgs !== undefined && numberOfArgs < 0) {
             numberOfArgs = undefined;
         }
         return await invoke('plugin:python|register_function', {
             payload: {
                 pythonFunctionCall,
-                numberOfArgs
+                numberOfArgs,
+                timeoutMs
             },
         }).then((r) => {
             registerJs(pythonFunctionCall, jsFunctionName);
             return r.value;
         });
     }
+    /**
+     * Removes a registered function, so it can't be called anymore, and `call.{jsFunctionName}`
+     * Only allowed while the `registration` policy of the plugin allows registering functions
+     *  @param {string} pythonFunctionCall - The python function call, as it was registered
+     *  @param {string} [jsFunctionName] - Name that was used in javascript, if it was given to `registerFunction`
+     */
+    async function unregisterFunction(pythonFunctionCall, jsFunctionName) {
+        return await invoke('plugin:python|unregister_function', {
+            payload: {
+                value: pythonFunctionCall,
+            },
+        }).then((r) => {
+            delete call[jsFunctionName ?? pythonFunctionCall.replaceAll(".", "_")];
+            return r.value;
+        });
+    }
     /**
      * No server invokation - assumes that function has already been registered server-side
      * Makes function available as `call.{jsFunctionName}`
@@ -90,21 +244,131 @@ var __TAURI_PLUGIN_PYTHON_API__ = (function (exports) {
         }
         call[jsFunctionName] = function (...args) { return callFunction(pythonFunctionCall, args); };
     }
+    /**
+     * cancels the running call `callId`; python code that is already running gets a `KeyboardInterrupt`
+     * and the call rejects with a `cancelled` error
+     */
+    async function cancelCall(callId) {
+        return invoke('plugin:python|cancel_call', {
+            payload: {
+                value: callId,
+            },
+        }).then((r) => {
+            return r.value;
+        });
+    }
+    async function cancellable(options, run) {
+        const signal = options?.signal;
+        const callId = options?.callId ?? (signal ? crypto.randomUUID() : undefined);
+        if (signal === undefined || callId === undefined) {
+            return run(callId);
+        }
+        signal.throwIfAborted();
+        // the call may have finished already, so a failing cancel is ignored
+        const onAbort = () => { cancelCall(callId).catch(() => { }); };
+        signal.addEventListener('abort', onAbort, { once: true });
+        try {
+            return await run(callId);
+        }
+        finally {
+            signal.removeEventListener('abort', onAbort);
+        }
+    }
     /**
      * calling previously registered function
+     *  @param {object} [kwargs] - Keyword arguments, passed to python as `**kwargs`
+     *  @param {CallOptions} [options] - Makes the call cancellable or sets its timeout
      */
-    async function callFunction(functionName, args) {
-        return invoke('plugin:python|call_function', {
+    async function callFunction(functionName, args, kwargs, options) {
+        return cancellable(options, (callId) => invoke('plugin:python|call_function', {
             payload: {
                 functionName,
                 args,
+                kwargs,
+                callId,
+                timeoutMs: options?.timeoutMs,
             },
         }).then((r) => {
             return r.value;
-        });
+        }));
+    }
+    /**
+     * calling previously registered function, keeping the JSON type of the result
+     * (a python `int` arrives as `number`, a `dict` as an object, `"30"` stays a string)
+     */
+    async function callFunctionJson(functionName, args, kwargs, options) {
+        return cancellable(options, (callId) => invoke('plugin:python|call_function_json', {
+            payload: {
+                functionName,
+                args,
+                kwargs,
+                callId,
+                timeoutMs: options?.timeoutMs,
+            },
+        }).then((r) => {
+            return r.value;
+        }));
+    }
+    /**
+     * calling previously registered generator function (`def` with `yield` or `async def` with `yield`)
+     * `onItem` is called with every yielded value, in order
+     * the generator is only advanced after `onItem` handled the previous batch of items
+     * resolves when the generator is exhausted and rejects if it raises
+     */
+    async function callFunctionStream(functionName, args, onItem, kwargs, options) {
+        const onEvent = new Channel();
+        onEvent.onmessage = (message) => {
+            if (message.event === 'item') {
+                onItem(message.data);
+            }
+            else if (message.event === 'batch') {
+                // lets the plugin pull the next batch; fails if the stream ended meanwhile
+                invoke('plugin:python|ack_stream', {
+                    payload: {
+                        channelId: onEvent.id,
+                        batch: message.data,
+                    },
+                }).catch(() => { });
+            }
+        };
+        await cancellable(options, (callId) => invoke('plugin:python|call_function_stream', {
+            payload: {
+                functionName,
+                args,
+                kwargs,
+                callId,
+                timeoutMs: options?.timeoutMs,
+            },
+            onEvent,
+        }));
+    }
+    async function getStatus() {
+        return invoke('plugin:python|get_status');
+    }
+    /**
+     * waits until python finished starting, see `backgroundInit`; rejects if it's still starting
+     * after `readyTimeoutSecs`
+     */
+    async function waitReady() {
+        return invoke('plugin:python|wait_ready');
+    }
+    /**
+     * the latest lines python printed, oldest first; requires `outputBufferLines` in the plugin config
+     */
+    async function getOutput() {
+        return invoke('plugin:python|get_output');
+    }
+    /**
+     * the registered functions whose `windows`/`origins` allow this window, with their signatures
+     * and docstrings; function scopes of capabilities aren't applied, so a listed function may still
+     * be rejected when called; requires the `allow-list-functions` permission
+     */
+    async function listFunctions() {
+        return invoke('plugin:python|list_functions');
     }
     /**
-     * read variable name directly from python
+     * read variable name directly from python; it must be listed in `_tauri_plugin_variables`
+     * of main.py (or exposed with `Builder::variable`), otherwise it rejects with a `notExposed` error
      */
     async function readVariable(value) {
         return invoke('plugin:python|read_variable', {
@@ -118,10 +382,18 @@ var __TAURI_PLUGIN_PYTHON_API__ = (function (exports) {
 
     exports.call = call;
     exports.callFunction = callFunction;
+    exports.callFunctionJson = callFunctionJson;
+    exports.callFunctionStream = callFunctionStream;
+    exports.cancelCall = cancelCall;
+    exports.getOutput = getOutput;
+    exports.getStatus = getStatus;
+    exports.listFunctions = listFunctions;
     exports.readVariable = readVariable;
     exports.registerFunction = registerFunction;
     exports.registerJs = registerJs;
     exports.runPython = runPython;
+    exports.unregisterFunction = unregisterFunction;
+    exports.waitReady = waitReady;
 
     return exports;
 
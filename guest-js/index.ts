@@ -4,7 +4,7 @@
  * git clone https://github.com/marcomq/tauri-plugin-python
 **/
 
-import { invoke } from '@tauri-apps/api/core'
+import { invoke, Channel } from '@tauri-apps/api/core'
 
 export let call: { [index: string]: Function } = {}; // array of functions
 
@@ -23,18 +23,21 @@ export async function runPython(code: string): Promise<string> {
  *  @param {string} pythonFunctionCall - The python function call, can contain one dot
  *  @param {number} [numberOfArgs] - Number of arguments, used for validation in python, use -1 to ignore this value
  *  @param {string} [jsFunctionName] - Name that is used in javascript: "call.jsFunctionName". Must not contain dots.
+ *  @param {number} [timeoutMs] - Default timeout for calls of this function, in milliseconds
  */
 export async function registerFunction(
   pythonFunctionCall: string,
   numberOfArgs?: number,
-  jsFunctionName?: string): Promise<string> {
+  jsFunctionName?: string,
+  timeoutMs?: number): Promise<string> {
   if (numberOfArgs !== undefined && numberOfArgs < 0) {
     numberOfArgs = undefined;
   }
   return await invoke<{ value: string }>('plugin:python|register_function', {
     payload: {
       pythonFunctionCall,
-      numberOfArgs
+      numberOfArgs,
+      timeoutMs
     },
   }).then((r: any) => {
     registerJs(pythonFunctionCall, jsFunctionName);
@@ -42,6 +45,23 @@ export async function registerFunction(
   });
 }
 
+/**
+ * Removes a registered function, so it can't be called anymore, and `call.{jsFunctionName}`
+ * Only allowed while the `registration` policy of the plugin allows registering functions
+ *  @param {string} pythonFunctionCall - The python function call, as it was registered
+ *  @param {string} [jsFunctionName] - Name that was used in javascript, if it was given to `registerFunction`
+ */
+export async function unregisterFunction(pythonFunctionCall: string, jsFunctionName?: string): Promise<string> {
+  return await invoke<{ value: string }>('plugin:python|unregister_function', {
+    payload: {
+      value: pythonFunctionCall,
+    },
+  }).then((r: any) => {
+    delete call[jsFunctionName ?? pythonFunctionCall.replaceAll(".", "_")];
+    return r.value;
+  });
+}
+
 /** 
  * No server invokation - assumes that function has already been registered server-side
  * Makes function available as `call.{jsFunctionName}`
@@ -55,22 +75,238 @@ export async function registerJs(pythonFunctionCall: string, jsFunctionName?: st
   call[jsFunctionName] = function (...args: any[]) { return callFunction(pythonFunctionCall, args) };
 }
 
+/**
+ * error the plugin commands reject with; `kind` tells what went wrong and `message` is readable
+ */
+export type PythonError =
+  | {
+    kind: 'pythonException', message: string, typeName: string, exceptionMessage: string,
+    traceback: string, frames: { file: string, line: number, function: string }[]
+  }
+  | { kind: 'timeout', message: string, function: string, timeoutMs: number }
+  | { kind: 'cancelled', message: string, callId: string }
+  | { kind: 'notRegistered', message: string, function: string }
+  | { kind: 'forbidden', message: string, function: string, webview: string }
+  | { kind: 'notExposed', message: string, variable: string }
+  | { kind: 'invalidArgument', message: string, function: string, parameter: string | null }
+  | { kind: 'registrationClosed' | 'interpreter' | 'io' | 'pluginInvoke' | 'error', message: string };
+
+/**
+ * options of a single call
+ *  @param {string} [callId] - Id to cancel the call with, see `cancelCall`; must be unique among running calls
+ *  @param {AbortSignal} [signal] - Cancels the call when aborted, a `callId` is generated if missing
+ *  @param {number} [timeoutMs] - Rejects the call after this many milliseconds, instead of the function's timeout
+ */
+export type CallOptions = {
+  callId?: string,
+  signal?: AbortSignal,
+  timeoutMs?: number,
+};
+
+/**
+ * cancels the running call `callId`; python code that is already running gets a `KeyboardInterrupt`
+ * and the call rejects with a `cancelled` error
+ */
+export async function cancelCall(callId: string): Promise<string> {
+  return invoke<{ value: string }>('plugin:python|cancel_call', {
+    payload: {
+      value: callId,
+    },
+  }).then((r: any) => {
+    return r.value;
+  });
+}
+
+async function cancellable<T>(
+  options: CallOptions | undefined,
+  run: (callId?: string) => Promise<T>): Promise<T> {
+  const signal = options?.signal;
+  const callId = options?.callId ?? (signal ? crypto.randomUUID() : undefined);
+  if (signal === undefined || callId === undefined) {
+    return run(callId);
+  }
+  signal.throwIfAborted();
+  // the call may have finished already, so a failing cancel is ignored
+  const onAbort = () => { cancelCall(callId).catch(() => { }); };
+  signal.addEventListener('abort', onAbort, { once: true });
+  try {
+    return await run(callId);
+  } finally {
+    signal.removeEventListener('abort', onAbort);
+  }
+}
+
 /**
  * calling previously registered function 
+ *  @param {object} [kwargs] - Keyword arguments, passed to python as `**kwargs`
+ *  @param {CallOptions} [options] - Makes the call cancellable or sets its timeout
  */
-export async function callFunction(functionName: string, args: any[]): Promise<string> {
-  return invoke<{ value: string }>('plugin:python|call_function', {
+export async function callFunction(
+  functionName: string,
+  args: any[],
+  kwargs?: { [name: string]: any },
+  options?: CallOptions): Promise<string> {
+  return cancellable(options, (callId) => invoke<{ value: string }>('plugin:python|call_function', {
     payload: {
       functionName,
       args,
+      kwargs,
+      callId,
+      timeoutMs: options?.timeoutMs,
     },
   }).then((r: any) => {
     return r.value;
-  });
+  }));
+}
+
+/**
+ * calling previously registered function, keeping the JSON type of the result
+ * (a python `int` arrives as `number`, a `dict` as an object, `"30"` stays a string)
+ */
+export async function callFunctionJson<T = unknown>(
+  functionName: string,
+  args: any[],
+  kwargs?: { [name: string]: any },
+  options?: CallOptions): Promise<T> {
+  return cancellable(options, (callId) => invoke<{ value: T }>('plugin:python|call_function_json', {
+    payload: {
+      functionName,
+      args,
+      kwargs,
+      callId,
+      timeoutMs: options?.timeoutMs,
+    },
+  }).then((r: any) => {
+    return r.value;
+  }));
+}
+
+/**
+ * message sent by the plugin while streaming a generator, see `callFunctionStream`
+ */
+export type StreamMessage<T> =
+  | { event: 'item', data: T }
+  | { event: 'batch', data: number }
+  | { event: 'done' }
+  | { event: 'error', data: string };
+
+/**
+ * calling previously registered generator function (`def` with `yield` or `async def` with `yield`)
+ * `onItem` is called with every yielded value, in order
+ * the generator is only advanced after `onItem` handled the previous batch of items
+ * resolves when the generator is exhausted and rejects if it raises
+ */
+export async function callFunctionStream<T = unknown>(
+  functionName: string,
+  args: any[],
+  onItem: (item: T) => void,
+  kwargs?: { [name: string]: any },
+  options?: CallOptions): Promise<void> {
+  const onEvent = new Channel<StreamMessage<T>>();
+  onEvent.onmessage = (message) => {
+    if (message.event === 'item') {
+      onItem(message.data);
+    } else if (message.event === 'batch') {
+      // lets the plugin pull the next batch; fails if the stream ended meanwhile
+      invoke('plugin:python|ack_stream', {
+        payload: {
+          channelId: onEvent.id,
+          batch: message.data,
+        },
+      }).catch(() => { });
+    }
+  };
+  await cancellable(options, (callId) => invoke('plugin:python|call_function_stream', {
+    payload: {
+      functionName,
+      args,
+      kwargs,
+      callId,
+      timeoutMs: options?.timeoutMs,
+    },
+    onEvent,
+  }));
+}
+
+/**
+ * whether python is usable: `starting`, `ready` or `failed`, with the initialization error
+ * (including the python traceback) in `error`
+ */
+export type Status =
+  | { status: 'starting' }
+  | { status: 'ready' }
+  | { status: 'failed', error: string };
+
+export async function getStatus(): Promise<Status> {
+  return invoke<Status>('plugin:python|get_status');
+}
+
+/**
+ * waits until python finished starting, see `backgroundInit`; rejects if it's still starting
+ * after `readyTimeoutSecs`
+ */
+export async function waitReady(): Promise<Status> {
+  return invoke<Status>('plugin:python|wait_ready');
+}
+
+/**
+ * a line python wrote to `sys.stdout` or `sys.stderr`
+ */
+export type OutputLine = { stream: 'stdout' | 'stderr', line: string };
+
+/**
+ * the latest lines python printed, oldest first; requires `outputBufferLines` in the plugin config
+ */
+export async function getOutput(): Promise<OutputLine[]> {
+  return invoke<OutputLine[]>('plugin:python|get_output');
+}
+
+/**
+ * a python type hint, as far as the plugin understands it; anything else is `any`
+ */
+export type TypeHint =
+  | { kind: 'any' | 'none' | 'bool' | 'int' | 'float' | 'str' }
+  | { kind: 'list' | 'iterator', items: TypeHint }
+  | { kind: 'tuple', items: TypeHint[] }
+  | { kind: 'dict', values: TypeHint }
+  | { kind: 'union', options: TypeHint[] }
+  | { kind: 'literal', values: (string | number | boolean | null)[] }
+  | { kind: 'object', name: string, fields: { name: string, type: TypeHint, required: boolean }[] };
+
+/**
+ * a registered python function, see `listFunctions`
+ */
+export type FunctionDescription = {
+  name: string,
+  /** null if the signature couldn't be inspected, e.g. with RustPython */
+  parameters: {
+    name: string,
+    kind: 'positional_only' | 'positional_or_keyword' | 'var_positional' | 'keyword_only' | 'var_keyword',
+    hasDefault: boolean,
+    annotation: TypeHint | null,
+    /** the default; null if there is none or it can't be passed as JSON, see `defaultRepr` */
+    default: unknown,
+    defaultRepr: string | null,
+  }[] | null,
+  returns: TypeHint | null,
+  doc: string | null,
+  isAsync: boolean,
+  isGenerator: boolean,
+  timeoutMs: number | null,
+};
+
+/**
+ * the registered functions whose `windows`/`origins` allow this window, with their signatures
+ * and docstrings; function scopes of capabilities aren't applied, so a listed function may still
+ * be rejected when called; requires the `allow-list-functions` permission
+ */
+export async function listFunctions(): Promise<FunctionDescription[]> {
+  return invoke<FunctionDescription[]>('plugin:python|list_functions');
 }
 
 /**
- * read variable name directly from python
+ * read variable name directly from python; it must be listed in `_tauri_plugin_variables`
+ * of main.py (or exposed with `Builder::variable`), otherwise it rejects with a `notExposed` error
  */
 export async function readVariable(value: string): Promise<string> {
   return invoke<{ value: string }>('plugin:python|read_variable', {
@@ -0,0 +1,207 @@
+//  Tauri Python Plugin
+//  © Copyright 2024, by Marco Mengelkoch
+//  Licensed under MIT License, see License file for more details
+//  git clone https://github.com/marcomq/tauri-plugin-python
+
+//! Checks the arguments of a call against the signature recorded at
+//! registration, so a wrong call from the UI fails with an error naming the
+//! parameter instead of a python `TypeError` - and before python is entered.
+
+use serde_json::{Map, Value};
+
+use crate::{
+    models::{ParameterInfo, ParameterKind, TypeHint},
+    Error, Result,
+};
+
+/// Rejects `args` and `kwargs` that don't fit `parameters`: too many or
+/// missing arguments, unknown or duplicate keywords, and values that can't
+/// have the type of the parameter's type hint.
+pub(crate) fn check(
+    function: &str,
+    parameters: &[ParameterInfo],
+    args: &[Value],
+    kwargs: &Map<String, Value>,
+) -> Result<()> {
+    let invalid = |parameter: Option<&str>, message: String| Error::InvalidArgument {
+        function: function.into(),
+        parameter: parameter.map(Into::into),
+        message,
+    };
+    let positional: Vec<&ParameterInfo> = parameters
+        .iter()
+        .filter(|p| {
+            matches!(
+                p.kind,
+                ParameterKind::PositionalOnly | ParameterKind::PositionalOrKeyword
+            )
+        })
+        .collect();
+    let var_positional = parameters
+        .iter()
+        .find(|p| p.kind == ParameterKind::VarPositional);
+    let var_keyword = parameters
+        .iter()
+        .find(|p| p.kind == ParameterKind::VarKeyword);
+
+    for (index, arg) in args.iter().enumerate() {
+        let Some(parameter) = positional.get(index).copied().or(var_positional) else {
+            return Err(invalid(
+                None,
+                format!(
+                    "Function '{function}' takes {} positional arguments but {} were given",
+                    positional.len(),
+                    args.len()
+                ),
+            ));
+        };
+        check_type(function, parameter, arg)?;
+    }
+    let filled_by_args = &positional[..args.len().min(positional.len())];
+    for (name, value) in kwargs {
+        let by_keyword = parameters.iter().find(|p| {
+            &p.name == name
+                && matches!(
+                    p.kind,
+                    ParameterKind::PositionalOrKeyword | ParameterKind::KeywordOnly
+                )
+        });
+        match (by_keyword, var_keyword) {
+            (Some(parameter), _) if filled_by_args.iter().any(|p| p.name == *name) => {
+                return Err(invalid(
+                    Some(&parameter.name),
+                    format!("Function '{function}' got multiple values for argument '{name}'"),
+                ));
+            }
+            (Some(parameter), _) | (None, Some(parameter)) => {
+                check_type(function, parameter, value)?
+            }
+            (None, None) => {
+                return Err(invalid(
+                    Some(name),
+                    format!("Function '{function}' has no keyword argument '{name}'"),
+                ));
+            }
+        }
+    }
+    for parameter in parameters.iter().filter(|p| !p.has_default) {
+        let filled = match parameter.kind {
+            ParameterKind::PositionalOnly => {
+                filled_by_args.iter().any(|p| p.name == parameter.name)
+            }
+            ParameterKind::PositionalOrKeyword => {
+                filled_by_args.iter().any(|p| p.name == parameter.name)
+                    || kwargs.contains_key(&parameter.name)
+            }
+            ParameterKind::KeywordOnly => kwargs.contains_key(&parameter.name),
+            ParameterKind::VarPositional | ParameterKind::VarKeyword => true,
+        };
+        if !filled {
+            return Err(invalid(
+                Some(&parameter.name),
+                format!(
+                    "Function '{function}' is missing the argument '{}'",
+                    parameter.name
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Rejects a `value` for `parameter` that can't have the type of its hint.
+/// For `*args` and `**kwargs`, the hint is the type of each value. `None`
+/// is also accepted for a parameter whose default is `None`.
+fn check_type(function: &str, parameter: &ParameterInfo, value: &Value) -> Result<()> {
+    let Some(hint) = &parameter.annotation else {
+        return Ok(());
+    };
+    if matches(hint, value)
+        || (value.is_null() && parameter.default_repr.as_deref() == Some("None"))
+    {
+        return Ok(());
+    }
+    Err(Error::InvalidArgument {
+        function: function.into(),
+        parameter: Some(parameter.name.clone()),
+        message: format!(
+            "Argument '{}' of function '{function}' must be {}, got {}",
+            parameter.name,
+            python_type(hint),
+            json_type(value)
+        ),
+    })
+}
+
+/// Whether a JSON `value` passed to python can have the type of `hint`.
+fn matches(hint: &TypeHint, value: &Value) -> bool {
+    match hint {
+        TypeHint::Any => true,
+        TypeHint::NoneType => value.is_null(),
+        TypeHint::Bool => value.is_boolean(),
+        TypeHint::Int => value.is_i64() || value.is_u64(),
+        TypeHint::Float => value.is_number(),
+        TypeHint::Str => value.is_string(),
+        TypeHint::List { items } | TypeHint::Iterator { items } => value
+            .as_array()
+            .is_some_and(|values| values.iter().all(|value| matches(items, value))),
+        TypeHint::Tuple { items } => value.as_array().is_some_and(|values| {
+            values.len() == items.len() && items.iter().zip(values).all(|(h, v)| matches(h, v))
+        }),
+        TypeHint::Dict { values } => value
+            .as_object()
+            .is_some_and(|object| object.values().all(|value| matches(values, value))),
+        TypeHint::Union { options } => options.iter().any(|option| matches(option, value)),
+        // Empty if python couldn't pass the literals as JSON.
+        TypeHint::Literal { values } => values.is_empty() || values.contains(value),
+        TypeHint::Object { fields, .. } => value.as_object().is_some_and(|object| {
+            fields.iter().all(|field| match object.get(&field.name) {
+                Some(value) => matches(&field.type_hint, value),
+                None => !field.required,
+            })
+        }),
+    }
+}
+
+/// `hint` as it's written in python, for error messages.
+fn python_type(hint: &TypeHint) -> String {
+    let list = |hints: &[TypeHint]| hints.iter().map(python_type).collect::<Vec<_>>().join(", ");
+    match hint {
+        TypeHint::Any => "Any".into(),
+        TypeHint::NoneType => "None".into(),
+        TypeHint::Bool => "bool".into(),
+        TypeHint::Int => "int".into(),
+        TypeHint::Float => "float".into(),
+        TypeHint::Str => "str".into(),
+        TypeHint::List { items } => format!("list[{}]", python_type(items)),
+        TypeHint::Iterator { items } => format!("Iterator[{}]", python_type(items)),
+        TypeHint::Tuple { items } => format!("tuple[{}]", list(items)),
+        TypeHint::Dict { values } => format!("dict[str, {}]", python_type(values)),
+        TypeHint::Union { options } => options
+            .iter()
+            .map(python_type)
+            .collect::<Vec<_>>()
+            .join(" | "),
+        TypeHint::Literal { values } => format!(
+            "Literal[{}]",
+            values
+                .iter()
+                .map(Value::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        TypeHint::Object { name, .. } => name.clone(),
+    }
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(n) if n.is_f64() => "a float",
+        Value::Number(_) => "an integer",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
@@ -0,0 +1,314 @@
+//  Tauri Python Plugin
+//  © Copyright 2024, by Marco Mengelkoch
+//  Licensed under MIT License, see License file for more details
+//  git clone https://github.com/marcomq/tauri-plugin-python
+
+//! TypeScript bindings of the registered python functions, generated from
+//! what [`crate::FunctionInfo`] recorded about them at registration.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    models::{FieldInfo, ParameterInfo, ParameterKind, TypeHint},
+    FunctionInfo,
+};
+
+/// Names that can't be used as TypeScript parameter or function names, plus
+/// `options` and `onItem`, which the wrappers take themselves, and the helper
+/// of the module.
+const RESERVED: &[&str] = &[
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "new",
+    "null",
+    "return",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "let",
+    "static",
+    "yield",
+    "await",
+    "options",
+    "onItem",
+    TRIM_ARGS,
+];
+
+/// Drops the trailing `undefined`s of positional arguments, so python uses the
+/// defaults of the optional parameters they stand for.
+const TRIM_ARGS: &str = "trimArgs";
+
+/// A TypeScript module with a wrapper of every function in `functions`. The
+/// `TypedDict`s and dataclasses of their type hints become interfaces.
+pub(crate) fn typescript(functions: &HashMap<String, FunctionInfo>) -> String {
+    let mut interfaces = BTreeMap::new();
+    let mut trims = false;
+    let mut names: Vec<&String> = functions.keys().collect();
+    names.sort();
+    let wrappers: Vec<String> = names
+        .into_iter()
+        .map(|name| wrapper(name, &functions[name], &mut interfaces, &mut trims))
+        .collect();
+
+    let mut module = String::from(
+        "// Generated by tauri-plugin-python from the registered python functions.\n\
+         // Do not edit, changes are overwritten.\n\
+         import { callFunctionJson, callFunctionStream, type CallOptions } from 'tauri-plugin-python-api';\n",
+    );
+    if trims {
+        module += &format!(
+            "\nfunction {TRIM_ARGS}(args: unknown[]): unknown[] {{\n  \
+             while (args.length > 0 && args[args.length - 1] === undefined) {{\n    \
+             args.pop();\n  }}\n  return args;\n}}\n"
+        );
+    }
+    for interface in interfaces.into_values() {
+        module += "\n";
+        module += &interface;
+    }
+    for wrapper in wrappers {
+        module += "\n";
+        module += &wrapper;
+    }
+    module
+}
+
+/// The wrapper of the function registered as `name`. Sets `trims` if it uses
+/// [`TRIM_ARGS`].
+fn wrapper(
+    name: &str,
+    info: &FunctionInfo,
+    interfaces: &mut BTreeMap<String, String>,
+    trims: &mut bool,
+) -> String {
+    let parameters = info.parameters.as_deref().unwrap_or_default();
+    // TypeScript doesn't allow optional parameters before required ones.
+    let last_required = parameters.iter().rposition(is_required);
+    // Parameters before `*args` can only be passed positionally along with it.
+    let var_positional = parameters
+        .iter()
+        .position(|parameter| parameter.kind == ParameterKind::VarPositional);
+    let mut optional_args = false;
+    let mut params = Vec::new();
+    let mut args = Vec::new();
+    let mut kwargs = Vec::new();
+    for (index, parameter) in parameters.iter().enumerate() {
+        let ident = identifier(&parameter.name);
+        let annotation = parameter.annotation.as_ref();
+        let ts = annotation.map_or("unknown".into(), |hint| ts_type(hint, interfaces));
+        let optional = !is_required(parameter);
+        let declared = match parameter.kind {
+            ParameterKind::VarPositional => format!("{}[]", wrap(&ts)),
+            ParameterKind::VarKeyword => format!("{{ [key: string]: {ts} }}"),
+            _ => ts,
+        };
+        params.push(match (optional, last_required) {
+            (false, _) => format!("{ident}: {declared}"),
+            (true, Some(last)) if index < last => format!("{ident}: {declared} | undefined"),
+            (true, _) => format!("{ident}?: {declared}"),
+        });
+        match parameter.kind {
+            ParameterKind::PositionalOnly => {
+                optional_args |= optional;
+                args.push(ident);
+            }
+            ParameterKind::PositionalOrKeyword
+                if !optional || var_positional.is_some_and(|position| index < position) =>
+            {
+                optional_args |= optional;
+                args.push(ident);
+            }
+            ParameterKind::VarPositional => args.push(format!("...({ident} ?? [])")),
+            ParameterKind::VarKeyword => kwargs.push(format!("...{ident}")),
+            // Passed by name, so leaving them out keeps the python default.
+            _ if ident == parameter.name => kwargs.push(ident),
+            _ => kwargs.push(format!("{}: {ident}", quoted(&parameter.name))),
+        }
+    }
+    let args = if optional_args {
+        *trims = true;
+        format!("{TRIM_ARGS}([{}])", args.join(", "))
+    } else {
+        format!("[{}]", args.join(", "))
+    };
+    let kwargs = if kwargs.is_empty() {
+        "undefined".to_string()
+    } else {
+        format!("{{ {} }}", kwargs.join(", "))
+    };
+
+    let mut wrapper = doc_comment(info.doc.as_deref());
+    let function = identifier(&name.replace('.', "_"));
+    let name = quoted(name);
+    if info.is_generator {
+        let item = match &info.returns {
+            Some(TypeHint::Iterator { items }) => ts_type(items, interfaces),
+            _ => "unknown".into(),
+        };
+        params.push(format!("onItem: (item: {item}) => void"));
+        params.push("options?: CallOptions".into());
+        wrapper += &format!(
+            "export async function {function}({}): Promise<void> {{\n  \
+             return callFunctionStream<{item}>({name}, {args}, onItem, {kwargs}, options);\n}}\n",
+            params.join(", ")
+        );
+    } else {
+        let returns = info
+            .returns
+            .as_ref()
+            .map_or("unknown".into(), |hint| ts_type(hint, interfaces));
+        params.push("options?: CallOptions".into());
+        wrapper += &format!(
+            "export async function {function}({}): Promise<{returns}> {{\n  \
+             return callFunctionJson<{returns}>({name}, {args}, {kwargs}, options);\n}}\n",
+            params.join(", ")
+        );
+    }
+    wrapper
+}
+
+/// Whether a caller has to pass `parameter`.
+fn is_required(parameter: &ParameterInfo) -> bool {
+    !parameter.has_default
+        && !matches!(
+            parameter.kind,
+            ParameterKind::VarPositional | ParameterKind::VarKeyword
+        )
+}
+
+/// The TypeScript type of `hint`. Objects are added to `interfaces` and
+/// referred to by name.
+fn ts_type(hint: &TypeHint, interfaces: &mut BTreeMap<String, String>) -> String {
+    match hint {
+        TypeHint::Any => "unknown".into(),
+        TypeHint::NoneType => "null".into(),
+        TypeHint::Bool => "boolean".into(),
+        TypeHint::Int | TypeHint::Float => "number".into(),
+        TypeHint::Str => "string".into(),
+        TypeHint::List { items } | TypeHint::Iterator { items } => {
+            format!("{}[]", wrap(&ts_type(items, interfaces)))
+        }
+        TypeHint::Tuple { items } => {
+            let items: Vec<String> = items.iter().map(|h| ts_type(h, interfaces)).collect();
+            format!("[{}]", items.join(", "))
+        }
+        TypeHint::Dict { values } => {
+            format!("{{ [key: string]: {} }}", ts_type(values, interfaces))
+        }
+        TypeHint::Union { options } => {
+            let mut types: Vec<String> = Vec::new();
+            for option in options {
+                let ts = ts_type(option, interfaces);
+                if !types.contains(&ts) {
+                    types.push(ts);
+                }
+            }
+            types.join(" | ")
+        }
+        TypeHint::Literal { values } if values.is_empty() => "unknown".into(),
+        TypeHint::Literal { values } => values
+            .iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>()
+            .join(" | "),
+        TypeHint::Object { name, fields } => {
+            let name = identifier(name);
+            if !interfaces.contains_key(&name) {
+                // Inserted first, so a recursive type refers to itself.
+                interfaces.insert(name.clone(), String::new());
+                let interface = interface(&name, fields, interfaces);
+                interfaces.insert(name.clone(), interface);
+            }
+            name
+        }
+    }
+}
+
+fn interface(
+    name: &str,
+    fields: &[FieldInfo],
+    interfaces: &mut BTreeMap<String, String>,
+) -> String {
+    let mut interface = format!("export interface {name} {{\n");
+    for field in fields {
+        let optional = if field.required { "" } else { "?" };
+        let property = if is_identifier(&field.name) {
+            field.name.clone()
+        } else {
+            quoted(&field.name)
+        };
+        let ts = ts_type(&field.type_hint, interfaces);
+        interface += &format!("  {property}{optional}: {ts};\n");
+    }
+    interface + "}\n"
+}
+
+/// Puts `ts` in parentheses if it's a union, e.g. for `(string | null)[]`.
+fn wrap(ts: &str) -> String {
+    if ts.contains(" | ") {
+        format!("({ts})")
+    } else {
+        ts.to_string()
+    }
+}
+
+fn doc_comment(doc: Option<&str>) -> String {
+    let Some(doc) = doc.map(str::trim).filter(|doc| !doc.is_empty()) else {
+        return String::new();
+    };
+    let mut comment = String::from("/**\n");
+    for line in doc.lines() {
+        let line = line.trim().replace("*/", "*\\/");
+        comment += &format!(" *{}{line}\n", if line.is_empty() { "" } else { " " });
+    }
+    comment + " */\n"
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c == '_' || c == '$' || c.is_alphabetic())
+        && chars.all(|c| c == '_' || c == '$' || c.is_alphanumeric())
+}
+
+/// `name` as a TypeScript identifier; python names are, unless reserved.
+fn identifier(name: &str) -> String {
+    if RESERVED.contains(&name) {
+        format!("{name}_")
+    } else {
+        name.to_string()
+    }
+}
+
+fn quoted(name: &str) -> String {
+    serde_json::Value::from(name).to_string()
+}
@@ -0,0 +1,285 @@
+//  Tauri Python Plugin
+//  © Copyright 2024, by Marco Mengelkoch
+//  Licensed under MIT License, see License file for more details
+//  git clone https://github.com/marcomq/tauri-plugin-python
+
+//! Calls from python into the plugin, made by the built-in `tauri` module.
+//!
+//! With PyO3, `_tauri_plugin_bridge.dispatch` is a native function, so a
+//! message is handled right away on the python worker thread. RustPython has no
+//! such hook; there, the `tauri` module queues its messages and the plugin
+//! delivers them after every call (see [`drain_outbox`]).
+
+use async_py::PyRunner;
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::PluginState;
+
+type Handler = dyn Fn(&str, Value) -> Result<Value, String> + Send + Sync;
+
+/// Handlers by bridge id. There is one per app, as python globals (and so the
+/// `tauri` module's bridge id) are per `PyRunner` while `sys.modules` is shared.
+fn handlers() -> &'static Mutex<HashMap<u64, Arc<Handler>>> {
+    static HANDLERS: OnceLock<Mutex<HashMap<u64, Arc<Handler>>>> = OnceLock::new();
+    HANDLERS.get_or_init(Default::default)
+}
+
+static NEXT_BRIDGE_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Registers the handler for messages from `app`'s python code and returns the
+/// id the `tauri` module has to pass along with them. The handler of the app's
+/// previous runner, if python is restarted, is removed.
+pub(crate) fn connect<R: Runtime>(app: &AppHandle<R>) -> u64 {
+    #[cfg(all(feature = "pyo3", not(feature = "rustpython")))]
+    if let Err(err) = native::install() {
+        log::error!("Error installing the python bridge: {err}");
+    }
+    let bridge_id = NEXT_BRIDGE_ID.fetch_add(1, Ordering::Relaxed);
+    let previous = app
+        .try_state::<PluginState>()
+        .map(|state| state.bridge_id.swap(bridge_id, Ordering::Relaxed));
+    let handler: Arc<Handler> = {
+        let app = app.clone();
+        Arc::new(move |kind, payload| handle(&app, kind, payload))
+    };
+    let mut handlers = handlers().lock().unwrap();
+    if let Some(previous) = previous {
+        handlers.remove(&previous);
+    }
+    handlers.insert(bridge_id, handler);
+    bridge_id
+}
+
+#[cfg(all(feature = "pyo3", not(feature = "rustpython")))]
+fn dispatch(bridge_id: u64, kind: &str, payload: Value) -> Result<Value, String> {
+    let handler = handlers()
+        .lock()
+        .unwrap()
+        .get(&bridge_id)
+        .cloned()
+        .ok_or_else(|| "The tauri module is not connected to an app".to_string())?;
+    handler(kind, payload)
+}
+
+#[derive(Deserialize)]
+struct CallRustMessage {
+    name: String,
+    args: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CallMessage {
+    call_id: String,
+    #[serde(default)]
+    thread_id: u64,
+}
+
+#[derive(Deserialize)]
+struct LogMessage {
+    level: u32,
+    name: String,
+    message: String,
+    file: Option<String>,
+    line: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct EmitMessage {
+    #[serde(default)]
+    target: Option<String>,
+    event: String,
+    payload: Value,
+}
+
+fn handle<R: Runtime>(app: &AppHandle<R>, kind: &str, payload: Value) -> Result<Value, String> {
+    match kind {
+        "emit" | "emit_to" => {
+            let message: EmitMessage =
+                serde_json::from_value(payload).map_err(|e| e.to_string())?;
+            match message.target {
+                Some(target) => app.emit_to(target.as_str(), &message.event, message.payload),
+                None => app.emit(&message.event, message.payload),
+            }
+            .map_err(|e| e.to_string())?;
+            Ok(Value::Null)
+        }
+        "call_rust" => {
+            let message: CallRustMessage =
+                serde_json::from_value(payload).map_err(|e| e.to_string())?;
+            let handler = app
+                .try_state::<PluginState>()
+                .and_then(|state| state.rust_handlers.get(&message.name).cloned());
+            // Failures of the handler itself are reported as `{"error": ..}`, so
+            // python can raise them as `tauri.RustError`.
+            Ok(match handler {
+                Some(handler) => match handler(message.args) {
+                    Ok(value) => serde_json::json!({ "value": value }),
+                    Err(err) => serde_json::json!({ "error": err }),
+                },
+                None => serde_json::json!({
+                    "error": format!("No Rust handler registered as '{}'", message.name)
+                }),
+            })
+        }
+        "log" => {
+            let message: LogMessage = serde_json::from_value(payload).map_err(|e| e.to_string())?;
+            log_record(message);
+            Ok(Value::Null)
+        }
+        "output" => {
+            let line = serde_json::from_value(payload).map_err(|e| e.to_string())?;
+            if let Some(state) = app.try_state::<PluginState>() {
+                state.output.record(app, line);
+            }
+            Ok(Value::Null)
+        }
+        "call_started" | "call_finished" => {
+            let message: CallMessage =
+                serde_json::from_value(payload).map_err(|e| e.to_string())?;
+            let state = app
+                .try_state::<PluginState>()
+                .ok_or_else(|| "The python plugin is not initialized".to_string())?;
+            if kind == "call_finished" {
+                state.calls.python_finished(&message.call_id);
+                return Ok(Value::Null);
+            }
+            Ok(state
+                .calls
+                .python_started(&message.call_id, message.thread_id)
+                .into())
+        }
+        _ => Err(format!("Unknown message '{kind}' from the tauri module")),
+    }
+}
+
+/// Passes a record of python's `logging` module to the `log` crate, with the
+/// logger name prefixed by `python::` as target.
+fn log_record(message: LogMessage) {
+    let level = match message.level {
+        40.. => log::Level::Error,
+        30.. => log::Level::Warn,
+        20.. => log::Level::Info,
+        10.. => log::Level::Debug,
+        _ => log::Level::Trace,
+    };
+    let target = format!("python::{}", message.name);
+    let logger = log::logger();
+    let metadata = log::Metadata::builder()
+        .level(level)
+        .target(&target)
+        .build();
+    if level > log::max_level() || !logger.enabled(&metadata) {
+        return;
+    }
+    logger.log(
+        &log::Record::builder()
+            .metadata(metadata)
+            .args(format_args!("{}", message.message))
+            .file(message.file.as_deref())
+            .line(message.line)
+            .build(),
+    );
+}
+
+/// Delivers the messages python queued during the last call, if there is no
+/// native bridge. A no-op with PyO3.
+pub(crate) async fn drain_outbox<R: Runtime>(app: &AppHandle<R>, runner: &PyRunner) {
+    if cfg!(all(feature = "pyo3", not(feature = "rustpython"))) {
+        return;
+    }
+    let Ok(Value::Array(messages)) = runner.call_function("tauri._drain", vec![]).await else {
+        return;
+    };
+    for message in messages {
+        let (Some(kind), Some(data)) = (message[0].as_str(), message[1].as_str()) else {
+            continue;
+        };
+        let payload = serde_json::from_str(data).unwrap_or_default();
+        if let Err(err) = handle(app, kind, payload) {
+            log::error!("Error handling '{kind}' from python: {err}");
+        }
+    }
+}
+
+/// Raises `KeyboardInterrupt` in the python thread `thread_id` as soon as it
+/// executes python code again, if `running()` - checked with the GIL held, so
+/// the call can't end in between - says it still runs the call. Blocks until
+/// the GIL is free. Only reachable with PyO3, as thread ids are only reported
+/// through the native bridge.
+pub(crate) fn interrupt_thread(thread_id: u64, running: impl FnOnce() -> bool) {
+    #[cfg(all(feature = "pyo3", not(feature = "rustpython")))]
+    native::set_async_exception(thread_id, running);
+    #[cfg(not(all(feature = "pyo3", not(feature = "rustpython"))))]
+    let _ = (thread_id, running);
+}
+
+/// Withdraws a pending [`interrupt_thread`] that didn't fire yet.
+pub(crate) fn clear_interrupt(thread_id: u64) {
+    #[cfg(all(feature = "pyo3", not(feature = "rustpython")))]
+    native::set_async_exception(thread_id, || false);
+    #[cfg(not(all(feature = "pyo3", not(feature = "rustpython"))))]
+    let _ = thread_id;
+}
+
+#[cfg(all(feature = "pyo3", not(feature = "rustpython")))]
+mod native {
+    use pyo3::{
+        exceptions::{PyRuntimeError, PyValueError},
+        prelude::*,
+        types::PyModule,
+    };
+
+    /// `_tauri_plugin_bridge.dispatch(bridge_id, kind, message)`, where
+    /// `message` and the returned string are JSON.
+    #[pyfunction]
+    fn dispatch(py: Python<'_>, bridge_id: u64, kind: &str, message: &str) -> PyResult<String> {
+        let payload =
+            serde_json::from_str(message).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        // The handler may block (e.g. on the main thread), so let other python
+        // threads run meanwhile.
+        let result = py
+            .detach(|| super::dispatch(bridge_id, kind, payload))
+            .map_err(PyRuntimeError::new_err)?;
+        Ok(result.to_string())
+    }
+
+    /// Sets `KeyboardInterrupt` as pending exception of a thread if
+    /// `interrupt()` returns `true`, and clears it otherwise.
+    pub(super) fn set_async_exception(thread_id: u64, interrupt: impl FnOnce() -> bool) {
+        Python::attach(|_py| {
+            let interrupt = interrupt();
+            // SAFETY: called with the GIL held; the exception type is a static
+            // object of the interpreter, and a null pointer clears the exception.
+            unsafe {
+                let exception = if interrupt {
+                    pyo3::ffi::PyExc_KeyboardInterrupt
+                } else {
+                    std::ptr::null_mut()
+                };
+                pyo3::ffi::PyThreadState_SetAsyncExc(thread_id as _, exception);
+            }
+        });
+    }
+
+    /// Adds `_tauri_plugin_bridge` to `sys.modules`. The interpreter is shared
+    /// by all `PyRunner`s, so this only has to work once, but is harmless to repeat.
+    pub(super) fn install() -> PyResult<()> {
+        Python::attach(|py| {
+            let module = PyModule::new(py, "_tauri_plugin_bridge")?;
+            module.add_function(wrap_pyfunction!(dispatch, &module)?)?;
+            py.import("sys")?
+                .getattr("modules")?
+                .set_item("_tauri_plugin_bridge", module)
+        })
+    }
+}
@@ -0,0 +1,327 @@
+//  Tauri Python Plugin
+//  © Copyright 2024, by Marco Mengelkoch
+//  Licensed under MIT License, see License file for more details
+//  git clone https://github.com/marcomq/tauri-plugin-python
+
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{atomic::Ordering, Arc, Mutex},
+    time::Duration,
+};
+use tauri::{
+    plugin::{Builder as PluginBuilder, TauriPlugin},
+    AppHandle, Emitter, Manager, Runtime, Wry,
+};
+
+use crate::{
+    bridge, build_runner, commands,
+    config::{Config, InitErrorPolicy, RegistrationPolicy, Startup},
+    init_python, install_log_handler,
+    interpreter::Interpreter,
+    models::{RegisterRequest, StatusResponse},
+    output::Output,
+    python_functions, register_python_functions, run_main_py, PluginState, PythonExt, RustHandler,
+};
+
+/// Builder for the python plugin, for apps that need more than [`crate::init`].
+///
+/// Paths, timeout and registration policy are read from the [`Config`] in
+/// `tauri.conf.json`; settings made on the builder take precedence.
+///
+/// ```rust,no_run
+/// tauri::Builder::default().plugin(
+///     tauri_plugin_python::Builder::new()
+///         .entry_file("app.py")
+///         .before_main("import os\nos.environ['APP_MODE'] = 'desktop'")
+///         .function("greet_python")
+///         .rust_handler("add", |args| {
+///             let sum: i64 = args.iter().filter_map(|v| v.as_i64()).sum();
+///             Ok(sum.into())
+///         })
+///         .on_ready(|_app| println!("python is ready"))
+///         .build(),
+/// );
+/// ```
+pub struct Builder<R: Runtime = Wry> {
+    functions: Vec<RegisterRequest>,
+    variables: Vec<String>,
+    rust_handlers: HashMap<String, Arc<RustHandler>>,
+    entry_file: Option<PathBuf>,
+    timeout: Option<Duration>,
+    before_main: Vec<String>,
+    background_init: Option<bool>,
+    on_ready: Option<OnReady<R>>,
+    typescript_bindings: Option<PathBuf>,
+}
+
+type OnReady<R> = Box<dyn FnOnce(&AppHandle<R>) + Send>;
+
+/// Event emitted once python is ready for calls.
+pub(crate) const READY_EVENT: &str = "python://ready";
+/// Event emitted if python couldn't be initialized, with the error as payload.
+pub(crate) const INIT_FAILED_EVENT: &str = "python://init-failed";
+
+impl<R: Runtime> Default for Builder<R> {
+    fn default() -> Self {
+        Self {
+            functions: Vec::new(),
+            variables: Vec::new(),
+            rust_handlers: HashMap::new(),
+            entry_file: None,
+            timeout: None,
+            before_main: Vec::new(),
+            background_init: None,
+            on_ready: None,
+            typescript_bindings: None,
+        }
+    }
+}
+
+impl<R: Runtime> Builder<R> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `path` at startup instead of the `entryModule` of the config. A
+    /// relative path is taken from the python source dir.
+    pub fn entry_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.entry_file = Some(path.into());
+        self
+    }
+
+    /// Default timeout of every call, replacing `timeoutSecs` of the config.
+    /// `Duration::ZERO` disables it.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Runs the python `code` at startup before the entry file, e.g. to set
+    /// environment variables or globals it depends on. Can be called multiple
+    /// times; the snippets run in order.
+    pub fn before_main(mut self, code: impl Into<String>) -> Self {
+        self.before_main.push(code.into());
+        self
+    }
+
+    /// Runs the entry file in the background, so the app starts without waiting
+    /// for it, replacing `backgroundInit` of the config. Calls made meanwhile
+    /// wait until python is ready.
+    pub fn background_init(mut self, enabled: bool) -> Self {
+        self.background_init = Some(enabled);
+        self
+    }
+
+    /// Calls `callback` once the entry file ran and all functions are registered.
+    pub fn on_ready<F>(mut self, callback: F) -> Self
+    where
+        F: FnOnce(&AppHandle<R>) + Send + 'static,
+    {
+        self.on_ready = Some(Box::new(callback));
+        self
+    }
+
+    /// In debug builds (`tauri dev`), writes a TypeScript module with a typed
+    /// wrapper of every registered function to `path` once python is ready,
+    /// e.g. `../src/python.ts`. The types are taken from the python type hints.
+    /// Does nothing in release builds.
+    pub fn typescript_bindings(mut self, path: impl Into<PathBuf>) -> Self {
+        self.typescript_bindings = Some(path.into());
+        self
+    }
+
+    /// Registers the python function `name` during startup, after `main.py` ran.
+    pub fn function(mut self, name: impl Into<String>) -> Self {
+        self.functions.push(RegisterRequest {
+            python_function_call: name.into(),
+            ..Default::default()
+        });
+        self
+    }
+
+    /// Like [`Builder::function`], but only the windows or webviews labeled
+    /// with one of `labels` may call it.
+    pub fn function_for_windows<I, L>(mut self, name: impl Into<String>, labels: I) -> Self
+    where
+        I: IntoIterator<Item = L>,
+        L: Into<String>,
+    {
+        self.functions.push(RegisterRequest {
+            python_function_call: name.into(),
+            windows: Some(labels.into_iter().map(Into::into).collect()),
+            ..Default::default()
+        });
+        self
+    }
+
+    /// Lets the frontend read the python global `name` with `readVariable`.
+    /// Other variables can only be read from Rust.
+    pub fn variable(mut self, name: impl Into<String>) -> Self {
+        self.variables.push(name.into());
+        self
+    }
+
+    /// Makes `handler` callable from python as `tauri.call_rust(name, *args)`.
+    ///
+    /// The handler gets the positional arguments as JSON values; an `Err` is
+    /// raised in python as `tauri.RustError`. It runs synchronously on the python
+    /// worker thread while the calling python code waits, so it must not call
+    /// back into python through the plugin. Requires the PyO3 backend.
+    pub fn rust_handler<F>(mut self, name: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(Vec<Value>) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        self.rust_handlers.insert(name.into(), Arc::new(handler));
+        self
+    }
+
+    pub fn build(self) -> TauriPlugin<R> {
+        let Self {
+            functions,
+            variables,
+            rust_handlers,
+            entry_file,
+            timeout,
+            before_main,
+            background_init,
+            on_ready,
+            typescript_bindings,
+        } = self;
+        let on_ready = match typescript_bindings {
+            Some(path) if cfg!(debug_assertions) => Some(Box::new(move |app: &AppHandle<R>| {
+                if let Err(err) = std::fs::write(&path, app.typescript_bindings()) {
+                    log::error!("Error writing '{}': {err}", path.display());
+                }
+                if let Some(on_ready) = on_ready {
+                    on_ready(app);
+                }
+            }) as OnReady<R>),
+            _ => on_ready,
+        };
+        PluginBuilder::<R, Option<Config>>::new("python")
+            .invoke_handler(tauri::generate_handler![
+                commands::run_python,
+                commands::register_function,
+                commands::unregister_function,
+                commands::call_function,
+                commands::call_function_json,
+                commands::call_function_stream,
+                commands::ack_stream,
+                commands::cancel_call,
+                commands::read_variable,
+                commands::get_status,
+                commands::wait_ready,
+                commands::get_output,
+                commands::list_functions
+            ])
+            .setup(move |app, api| {
+                let config = api.config().clone().unwrap_or_default();
+                #[cfg(mobile)]
+                let python = crate::mobile::init(app, api)?;
+                #[cfg(desktop)]
+                let python = crate::desktop::init(app, api)?;
+                app.manage(python);
+                app.manage(PluginState {
+                    rust_handlers,
+                    variables: Mutex::new(variables.into_iter().collect()),
+                    registration: config.registration,
+                    ready_timeout: Duration::from_secs(config.ready_timeout_secs),
+                    output: Output::new(config.output_buffer_lines, config.emit_output),
+                    ..Default::default()
+                });
+
+                let mut startup = Startup::resolve(app, &config, entry_file.as_deref());
+                if let Some(timeout) = timeout {
+                    startup.timeout = (!timeout.is_zero()).then_some(timeout);
+                }
+                startup.before_main = before_main;
+                app.manage(Interpreter::new(
+                    build_runner(startup.timeout),
+                    startup.clone(),
+                ));
+                if background_init.unwrap_or(config.background_init) {
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let result = start_python(&app, &startup, functions).await;
+                        if let Err(err) = &result {
+                            log::error!("Python failed to initialize: {err}");
+                        }
+                        started(&app, config.registration, &result, on_ready);
+                    });
+                    return Ok(());
+                }
+                let result = tokio::runtime::Runtime::new()?
+                    .block_on(start_python(app, &startup, functions));
+                started(app, config.registration, &result, on_ready);
+                match result {
+                    Err(err) if config.on_init_error == InitErrorPolicy::Fail => Err(err.into()),
+                    Err(err) => {
+                        log::error!("Python failed to initialize: {err}");
+                        Ok(())
+                    }
+                    Ok(()) => Ok(()),
+                }
+            })
+            .build()
+    }
+}
+
+/// Runs the entry module, registers the functions of the builder and those
+/// main.py asks for (see [`python_functions`]) and exposes the variables of
+/// `_tauri_plugin_variables`.
+pub(crate) async fn start_python<R: Runtime>(
+    app: &AppHandle<R>,
+    startup: &Startup,
+    functions: Vec<RegisterRequest>,
+) -> crate::Result<()> {
+    let runner = &app.runner();
+    init_python(app, runner, startup).await?;
+    if let Some(main_py) = &startup.main_py {
+        run_main_py(runner, main_py).await?;
+    }
+    install_log_handler(runner, startup).await?;
+    bridge::drain_outbox(app, runner).await;
+    register_python_functions(app, functions).await?;
+    register_python_functions(app, python_functions(runner).await?).await?;
+    let variables = runner
+        .read_variable("_tauri_plugin_variables")
+        .await
+        .unwrap_or_default();
+    if let Ok(variables) = serde_json::from_value::<Vec<String>>(variables) {
+        let state = app.state::<PluginState>();
+        state.variables.lock().unwrap().extend(variables);
+    }
+    Ok(())
+}
+
+/// Records the outcome of [`start_python`], tells the frontend and, if python
+/// is ready, calls `on_ready`.
+pub(crate) fn started<R: Runtime>(
+    app: &AppHandle<R>,
+    registration: RegistrationPolicy,
+    result: &crate::Result<()>,
+    on_ready: Option<OnReady<R>>,
+) {
+    let state = app.state::<PluginState>();
+    match result {
+        Ok(()) => {
+            if registration == RegistrationPolicy::FreezeAfterSetup {
+                state.init_blocked.store(true, Ordering::Relaxed);
+            }
+            state.status.send_replace(StatusResponse::Ready);
+            let _ = app.emit(READY_EVENT, ());
+            if let Some(on_ready) = on_ready {
+                on_ready(app);
+            }
+        }
+        Err(err) => {
+            let error = err.report();
+            state.status.send_replace(StatusResponse::Failed {
+                error: error.clone(),
+            });
+            let _ = app.emit(INIT_FAILED_EVENT, serde_json::json!({ "error": error }));
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//  Tauri Python Plugin
+//  © Copyright 2024, by Marco Mengelkoch
+//  Licensed under MIT License, see License file for more details
+//  git clone https://github.com/marcomq/tauri-plugin-python
+
+//! Calls that can be interrupted: those that carry a call id, so they can be
+//! cancelled via `cancel_call`, and synchronous calls, which are interrupted
+//! once they timed out. A webview can only cancel the calls it made itself.
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+use tokio::sync::watch;
+
+use crate::{bridge, Error};
+
+/// Prefix of the ids of calls made without one, which only the plugin cancels.
+const UNNAMED_PREFIX: &str = "_tauri_plugin_";
+
+struct RunningCall {
+    cancel: watch::Sender<bool>,
+    /// The webview that made the call, `None` if it was made from Rust.
+    webview: Option<String>,
+    /// The python thread running the call, while it runs. Only known with the
+    /// native bridge, which reports it via `tauri._call_started`.
+    thread_id: Option<u64>,
+    /// The caller is gone, but python is still running the call.
+    detached: bool,
+}
+
+/// Shared with the tasks interrupting cancelled calls, see [`Calls::cancel`].
+#[derive(Default)]
+pub(crate) struct Calls(Arc<Mutex<HashMap<String, RunningCall>>>);
+
+impl Calls {
+    /// Tracks the call `call_id`, made by `webview`, until the returned guard
+    /// is dropped.
+    pub(crate) fn start(
+        &self,
+        call_id: &str,
+        webview: Option<&str>,
+    ) -> crate::Result<CallGuard<'_>> {
+        check_call_id(call_id)?;
+        self.insert(call_id, webview)
+    }
+
+    /// Like [`Calls::start`], for a call the caller gave no id.
+    pub(crate) fn start_unnamed(&self) -> CallGuard<'_> {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        loop {
+            let call_id = format!(
+                "{UNNAMED_PREFIX}{}",
+                NEXT_ID.fetch_add(1, Ordering::Relaxed)
+            );
+            if let Ok(guard) = self.insert(&call_id, None) {
+                return guard;
+            }
+        }
+    }
+
+    fn insert(&self, call_id: &str, webview: Option<&str>) -> crate::Result<CallGuard<'_>> {
+        let mut calls = self.0.lock().unwrap();
+        if calls.contains_key(call_id) {
+            return Err(Error::String(format!(
+                "Call id '{call_id}' is already in use"
+            )));
+        }
+        let (cancel, cancelled) = watch::channel(false);
+        calls.insert(
+            call_id.to_string(),
+            RunningCall {
+                cancel,
+                webview: webview.map(str::to_string),
+                thread_id: None,
+                detached: false,
+            },
+        );
+        Ok(CallGuard {
+            calls: self,
+            call_id: call_id.to_string(),
+            cancelled,
+        })
+    }
+
+    /// Cancels the call: a waiting caller is released right away, and python
+    /// code that is already running gets a `KeyboardInterrupt`. A `webview`
+    /// can only cancel its own calls, `None` cancels any. Returns `false` if
+    /// there is no such call (anymore) for `webview`.
+    pub(crate) fn cancel(&self, call_id: &str, webview: Option<&str>) -> bool {
+        let thread_id = {
+            let calls = self.0.lock().unwrap();
+            let Some(call) = calls.get(call_id) else {
+                return false;
+            };
+            if webview.is_some() && call.webview.as_deref() != webview {
+                return false;
+            }
+            call.cancel.send_replace(true);
+            call.thread_id
+        };
+        if let Some(thread_id) = thread_id {
+            // Interrupting waits for the GIL, which python may hold for long
+            // in a C function, so it's done on a blocking thread.
+            let calls = self.0.clone();
+            let call_id = call_id.to_string();
+            tauri::async_runtime::spawn_blocking(move || {
+                bridge::interrupt_thread(thread_id, || {
+                    let calls = calls.lock().unwrap();
+                    calls
+                        .get(&call_id)
+                        .is_some_and(|call| call.thread_id == Some(thread_id))
+                });
+            });
+        }
+        true
+    }
+
+    /// Called by python right before running the call on `thread_id`. Returns
+    /// `false` if the call was cancelled while it was queued, so python skips it.
+    pub(crate) fn python_started(&self, call_id: &str, thread_id: u64) -> bool {
+        let mut calls = self.0.lock().unwrap();
+        match calls.get_mut(call_id) {
+            Some(call) if !*call.cancel.borrow() => {
+                call.thread_id = Some(thread_id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Called by python once the call returned; it can't be interrupted anymore.
+    pub(crate) fn python_finished(&self, call_id: &str) {
+        let mut calls = self.0.lock().unwrap();
+        let Some(call) = calls.get_mut(call_id) else {
+            return;
+        };
+        let thread_id = call.thread_id.take();
+        if call.detached {
+            calls.remove(call_id);
+        }
+        drop(calls);
+        if let Some(thread_id) = thread_id {
+            // A cancellation that raced with the end of the call must not hit
+            // whatever runs next on this thread.
+            bridge::clear_interrupt(thread_id);
+        }
+    }
+}
+
+/// Rejects the ids reserved for calls made without one, see
+/// [`Calls::start_unnamed`].
+pub(crate) fn check_call_id(call_id: &str) -> crate::Result<()> {
+    if call_id.starts_with(UNNAMED_PREFIX) {
+        return Err(Error::String(format!(
+            "Call id '{call_id}' is reserved for the plugin"
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) struct CallGuard<'a> {
+    calls: &'a Calls,
+    call_id: String,
+    cancelled: watch::Receiver<bool>,
+}
+
+impl CallGuard<'_> {
+    pub(crate) fn call_id(&self) -> &str {
+        &self.call_id
+    }
+
+    /// Runs `future` until it completes or the call is cancelled (`Err`).
+    pub(crate) async fn unless_cancelled<T>(
+        &mut self,
+        future: impl Future<Output = T>,
+    ) -> crate::Result<T> {
+        tokio::select! {
+            output = future => Ok(output),
+            _ = self.cancelled.wait_for(|cancelled| *cancelled) => {
+                Err(Error::Cancelled(self.call_id.clone()))
+            }
+        }
+    }
+}
+
+impl Drop for CallGuard<'_> {
+    fn drop(&mut self) {
+        let mut calls = self.calls.0.lock().unwrap();
+        match calls.get_mut(&self.call_id) {
+            // Still running in python, e.g. blocked in a C function that can't
+            // be interrupted; `python_finished` cleans up.
+            Some(call) if call.thread_id.is_some() => call.detached = true,
+            _ => {
+                calls.remove(&self.call_id);
+            }
+        }
+    }
+}
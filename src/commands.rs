@@ -3,11 +3,59 @@
 //  Licensed under MIT License, see License file for more details
 //  git clone https://github.com/marcomq/tauri-plugin-python
 
-use tauri::{command, AppHandle, Runtime};
+use tauri::{
+    command,
+    ipc::{Channel, CommandScope, GlobalScope},
+    AppHandle, Manager, Runtime, Webview,
+};
 
 use crate::models::*;
-use crate::PythonExt;
+use crate::scope::FunctionScope;
 use crate::Result;
+use crate::{
+    cancel_running_call, check_access, check_scope, check_variable, ready, stream_function,
+};
+use crate::{PluginState, PythonExt};
+
+/// Checks that `webview` may call `function_name`, both by the function's
+/// registration and by the scopes of its capabilities.
+fn authorize<R: Runtime>(
+    app: &AppHandle<R>,
+    webview: &Webview<R>,
+    function_name: &str,
+    command_scope: &CommandScope<FunctionScope>,
+    global_scope: &GlobalScope<FunctionScope>,
+) -> Result<()> {
+    check_access(app, webview, function_name)?;
+    let allows: Vec<&FunctionScope> = command_scope
+        .allows()
+        .iter()
+        .chain(global_scope.allows())
+        .map(AsRef::as_ref)
+        .collect();
+    let denies: Vec<&FunctionScope> = command_scope
+        .denies()
+        .iter()
+        .chain(global_scope.denies())
+        .map(AsRef::as_ref)
+        .collect();
+    check_scope(function_name, webview.label(), &allows, &denies)
+}
+
+/// Rejects changes of the registered functions from the frontend with
+/// [`RegistrationPolicy::RustOnly`](crate::RegistrationPolicy::RustOnly).
+fn check_frontend_registration<R: Runtime>(
+    app: &AppHandle<R>,
+    operation: &'static str,
+) -> Result<()> {
+    if app.state::<PluginState>().registration == crate::RegistrationPolicy::RustOnly {
+        return Err(crate::Error::RegistrationClosed {
+            operation,
+            reason: "from the frontend",
+        });
+    }
+    Ok(())
+}
 
 #[command]
 pub(crate) async fn run_python<R: Runtime>(
@@ -21,19 +69,130 @@ pub(crate) async fn register_function<R: Runtime>(
     app: AppHandle<R>,
     payload: RegisterRequest,
 ) -> Result<StringResponse> {
+    check_frontend_registration(&app, "register")?;
     app.register_function(payload).await
 }
 #[command]
+pub(crate) async fn unregister_function<R: Runtime>(
+    app: AppHandle<R>,
+    payload: StringRequest,
+) -> Result<StringResponse> {
+    check_frontend_registration(&app, "unregister")?;
+    app.unregister_function(payload).await
+}
+#[command]
 pub(crate) async fn call_function<R: Runtime>(
     app: AppHandle<R>,
-    payload: RunRequest,
+    webview: Webview<R>,
+    command_scope: CommandScope<FunctionScope>,
+    global_scope: GlobalScope<FunctionScope>,
+    mut payload: RunRequest,
 ) -> Result<StringResponse> {
+    authorize(
+        &app,
+        &webview,
+        &payload.function_name,
+        &command_scope,
+        &global_scope,
+    )?;
+    payload.webview = Some(webview.label().into());
     app.call_function(payload).await
 }
 #[command]
+pub(crate) async fn call_function_json<R: Runtime>(
+    app: AppHandle<R>,
+    webview: Webview<R>,
+    command_scope: CommandScope<FunctionScope>,
+    global_scope: GlobalScope<FunctionScope>,
+    mut payload: RunRequest,
+) -> Result<ValueResponse> {
+    authorize(
+        &app,
+        &webview,
+        &payload.function_name,
+        &command_scope,
+        &global_scope,
+    )?;
+    payload.webview = Some(webview.label().into());
+    app.call_function_json(payload).await
+}
+#[command]
+pub(crate) async fn call_function_stream<R: Runtime>(
+    app: AppHandle<R>,
+    webview: Webview<R>,
+    command_scope: CommandScope<FunctionScope>,
+    global_scope: GlobalScope<FunctionScope>,
+    mut payload: RunRequest,
+    on_event: Channel<StreamMessage>,
+) -> Result<()> {
+    authorize(
+        &app,
+        &webview,
+        &payload.function_name,
+        &command_scope,
+        &global_scope,
+    )?;
+    payload.webview = Some(webview.label().into());
+    stream_function(&app, payload, on_event, true).await
+}
+/// Lets a stream started by `call_function_stream` go on with its next batch.
+#[command]
+pub(crate) async fn ack_stream<R: Runtime>(
+    app: AppHandle<R>,
+    payload: StreamAck,
+) -> Result<StringResponse> {
+    let state = app.state::<PluginState>();
+    if !state.streams.ack(payload.channel_id, payload.batch) {
+        return Err(crate::Error::String(format!(
+            "No running stream on channel {}",
+            payload.channel_id
+        )));
+    }
+    Ok(StringResponse { value: "Ok".into() })
+}
+/// Cancels only calls that `webview` made.
+#[command]
+pub(crate) async fn cancel_call<R: Runtime>(
+    app: AppHandle<R>,
+    webview: Webview<R>,
+    payload: StringRequest,
+) -> Result<StringResponse> {
+    let state = app.state::<PluginState>();
+    cancel_running_call(&state, &payload.value, Some(webview.label()))
+}
+#[command]
+pub(crate) async fn get_status<R: Runtime>(app: AppHandle<R>) -> Result<StatusResponse> {
+    app.get_status().await
+}
+#[command]
+pub(crate) async fn wait_ready<R: Runtime>(app: AppHandle<R>) -> Result<StatusResponse> {
+    app.wait_ready().await
+}
+#[command]
+pub(crate) async fn get_output<R: Runtime>(app: AppHandle<R>) -> Result<Vec<OutputLine>> {
+    app.get_output().await
+}
+/// Lists only the functions whose registration allows `webview`. The scopes of
+/// the `allow-call` capabilities aren't applied, so some of them may still be
+/// rejected when called.
+#[command]
+pub(crate) async fn list_functions<R: Runtime>(
+    app: AppHandle<R>,
+    webview: Webview<R>,
+) -> Result<Vec<FunctionDescription>> {
+    let functions = app.list_functions().await?;
+    Ok(functions
+        .into_iter()
+        .filter(|function| check_access(&app, &webview, &function.name).is_ok())
+        .collect())
+}
+#[command]
 pub(crate) async fn read_variable<R: Runtime>(
     app: AppHandle<R>,
     payload: StringRequest,
 ) -> Result<StringResponse> {
+    // `_tauri_plugin_variables` is only known once main.py ran.
+    ready(app.state::<PluginState>().inner()).await?;
+    check_variable(&app, &payload.value)?;
     app.read_variable(payload).await
 }
@@ -0,0 +1,208 @@
+//  Tauri Python Plugin
+//  © Copyright 2024, by Marco Mengelkoch
+//  Licensed under MIT License, see License file for more details
+//  git clone https://github.com/marcomq/tauri-plugin-python
+
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tauri::{path::BaseDirectory, AppHandle, Manager, Runtime};
+
+use crate::DEFAULT_TIMEOUT_SECS;
+
+const DEFAULT_READY_TIMEOUT_SECS: u64 = 60;
+const DEFAULT_RESTART_AFTER_SECS: u64 = 10;
+
+/// Configuration of the plugin, read from `plugins.python` in `tauri.conf.json`.
+/// Every field is optional:
+///
+/// ```json
+/// "plugins": {
+///   "python": {
+///     "srcDir": "src-python",
+///     "entryModule": "main",
+///     "sysPath": ["vendor"],
+///     "venv": ".venv",
+///     "timeoutSecs": 300,
+///     "restartAfterSecs": 10,
+///     "registration": "freezeOnFirstCall",
+///     "onInitError": "fail",
+///     "backgroundInit": false,
+///     "readyTimeoutSecs": 60,
+///     "outputBufferLines": 0,
+///     "emitOutput": false,
+///     "logLevel": "warning"
+///   }
+/// }
+/// ```
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+pub struct Config {
+    /// Directory of the python sources. Relative paths are looked up in the
+    /// app resources first and then, for development, in the working directory.
+    pub src_dir: PathBuf,
+    /// Module run at startup, e.g. `app.main` for `app/main.py` in `src_dir`.
+    pub entry_module: String,
+    /// Extra `sys.path` entries, relative to `src_dir`.
+    pub sys_path: Vec<PathBuf>,
+    /// The venv loaded with the `venv` feature, relative to `src_dir`.
+    pub venv: PathBuf,
+    /// Default timeout of every call in seconds, `0` disables it. The
+    /// `TAURI_PLUGIN_PYTHON_TIMEOUT_SECS` env var takes precedence.
+    pub timeout_secs: u64,
+    /// How long the worker may stay busy after a call timed out before python
+    /// is restarted, `0` never restarts it for that.
+    pub restart_after_secs: u64,
+    pub registration: RegistrationPolicy,
+    pub on_init_error: InitErrorPolicy,
+    /// Runs the entry module in the background instead of delaying the app
+    /// start until it finished.
+    pub background_init: bool,
+    /// How long calls made before python is ready wait for it.
+    pub ready_timeout_secs: u64,
+    /// How many of the latest lines printed by python are kept for
+    /// `get_output`, `0` keeps none.
+    pub output_buffer_lines: usize,
+    /// Emits every line printed by python as `python://output` event.
+    pub emit_output: bool,
+    /// Minimum level of the records of python's `logging` module that are
+    /// passed to the `log` crate.
+    pub log_level: LogLevel,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            src_dir: "src-python".into(),
+            entry_module: "main".into(),
+            sys_path: Vec::new(),
+            venv: ".venv".into(),
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+            restart_after_secs: DEFAULT_RESTART_AFTER_SECS,
+            registration: RegistrationPolicy::default(),
+            on_init_error: InitErrorPolicy::default(),
+            background_init: false,
+            ready_timeout_secs: DEFAULT_READY_TIMEOUT_SECS,
+            output_buffer_lines: 0,
+            emit_output: false,
+            log_level: LogLevel::default(),
+        }
+    }
+}
+
+/// Until when and from where functions can be registered via
+/// `register_function`, and unregistered via `unregister_function`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RegistrationPolicy {
+    /// Until the first function is called.
+    #[default]
+    FreezeOnFirstCall,
+    /// Only during startup, i.e. from `main.py` and the plugin builder.
+    FreezeAfterSetup,
+    /// At any time, e.g. for feature modules that are loaded lazily.
+    Never,
+    /// At any time, but only from Rust (including `main.py` and the plugin
+    /// builder); the frontend commands are always rejected.
+    RustOnly,
+}
+
+/// What happens if python can't be initialized, e.g. because the entry module
+/// raised an error.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InitErrorPolicy {
+    /// The plugin setup fails, so the app doesn't start.
+    #[default]
+    Fail,
+    /// The app starts anyway. Calls fail with the initialization error, which
+    /// is also reported by `get_status`. Always the case with `background_init`,
+    /// as the app is running already.
+    Degraded,
+}
+
+/// A level of python's `logging` module.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    /// Python's default.
+    #[default]
+    Warning,
+    Error,
+    Critical,
+    /// Doesn't install the handler at all.
+    Off,
+}
+
+impl LogLevel {
+    /// The numeric level in python, `None` for [`LogLevel::Off`].
+    pub(crate) fn python_level(self) -> Option<u32> {
+        match self {
+            LogLevel::Debug => Some(10),
+            LogLevel::Info => Some(20),
+            LogLevel::Warning => Some(30),
+            LogLevel::Error => Some(40),
+            LogLevel::Critical => Some(50),
+            LogLevel::Off => None,
+        }
+    }
+}
+
+/// How python is started, resolved from [`Config`] during setup and kept for
+/// restarts.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Startup {
+    /// The source directory, first entry added to `sys.path`.
+    pub(crate) dir: PathBuf,
+    pub(crate) main_py: Option<PathBuf>,
+    pub(crate) sys_path: Vec<PathBuf>,
+    pub(crate) venv: PathBuf,
+    pub(crate) timeout: Option<Duration>,
+    /// How long a busy worker gets after a timeout, `None` if it's never
+    /// restarted for being busy.
+    pub(crate) restart_after: Option<Duration>,
+    /// Python code run before the entry file.
+    pub(crate) before_main: Vec<String>,
+    pub(crate) log_level: LogLevel,
+}
+
+impl Startup {
+    /// Resolves the paths of `config`; `entry_file` replaces its `entry_module`.
+    pub(crate) fn resolve<R: Runtime>(
+        app: &AppHandle<R>,
+        config: &Config,
+        entry_file: Option<&Path>,
+    ) -> Self {
+        let entry_file = entry_file.map(Path::to_path_buf).unwrap_or_else(|| {
+            PathBuf::from(config.entry_module.replace('.', "/")).with_extension("py")
+        });
+        let mut dir = app
+            .path()
+            .resolve(&config.src_dir, BaseDirectory::Resource)
+            .unwrap_or_default();
+        if !dir.join(&entry_file).exists() {
+            log::warn!(
+                "'{}' seems not to be registered in 'tauri.conf.json'",
+                config.src_dir.join(&entry_file).display()
+            );
+            dir = std::env::current_dir()
+                .unwrap_or_default()
+                .join(&config.src_dir);
+        }
+        Self {
+            main_py: Some(dir.join(&entry_file)),
+            sys_path: config.sys_path.iter().map(|path| dir.join(path)).collect(),
+            venv: dir.join(&config.venv),
+            timeout: (config.timeout_secs > 0).then(|| Duration::from_secs(config.timeout_secs)),
+            restart_after: (config.restart_after_secs > 0)
+                .then(|| Duration::from_secs(config.restart_after_secs)),
+            before_main: Vec::new(),
+            log_level: config.log_level,
+            dir,
+        }
+    }
+}
@@ -4,7 +4,10 @@
 //  git clone https://github.com/marcomq/tauri-plugin-python
 use async_py::PyRunnerError;
 
-use serde::{ser::Serializer, Serialize};
+use serde::{
+    ser::{SerializeMap, Serializer},
+    Deserialize, Serialize,
+};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -17,16 +20,168 @@ pub enum Error {
     #[cfg(mobile)]
     #[error(transparent)]
     PluginInvoke(#[from] tauri::plugin::mobile::PluginInvokeError),
+    #[error("Call '{0}' was cancelled")]
+    Cancelled(String),
+    #[error("Function '{function}' timed out after {timeout_ms} ms")]
+    Timeout { function: String, timeout_ms: u64 },
+    /// An exception raised by python code called by the plugin.
+    #[error("{context}: {type_name}: {message}")]
+    PythonException {
+        /// What the plugin was doing, e.g. `Error calling Python function 'f'`.
+        context: String,
+        type_name: String,
+        message: String,
+        /// The formatted traceback, empty if python couldn't provide one.
+        traceback: String,
+        frames: Vec<PythonFrame>,
+    },
+    #[error("Function {0} has not been registered yet")]
+    NotRegistered(String),
+    /// The calling webview isn't allowed to call the function.
+    #[error("Function '{function}' may not be called from webview '{webview}'")]
+    Forbidden { function: String, webview: String },
+    /// The arguments of a call don't fit the signature of the function.
+    #[error("{message}")]
+    InvalidArgument {
+        function: String,
+        /// The offending parameter, `None` if there are too many arguments.
+        parameter: Option<String>,
+        message: String,
+    },
+    /// The frontend read a variable that isn't exposed to it.
+    #[error("Variable '{0}' is not exposed to the frontend")]
+    NotExposed(String),
+    /// `register_function` or `unregister_function` was called after, or from
+    /// where, the registration policy doesn't allow it.
+    #[error("Cannot {operation} {reason}")]
+    RegistrationClosed {
+        /// `register` or `unregister`.
+        operation: &'static str,
+        reason: &'static str,
+    },
+    /// The python runner stopped responding or is gone.
+    #[error("Error: {0}")]
+    Interpreter(String),
     #[error(transparent)]
     PyRunner(#[from] PyRunnerError),
 }
 
+/// A frame of the traceback of a [`Error::PythonException`], outermost first.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PythonFrame {
+    pub file: String,
+    pub line: u32,
+    pub function: String,
+}
+
+/// Details of an exception, as sent by `_tauri_plugin_exception` in python.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExceptionDetails {
+    type_name: String,
+    message: String,
+    #[serde(default)]
+    traceback: String,
+    #[serde(default)]
+    frames: Vec<PythonFrame>,
+}
+
+impl Error {
+    /// Stable code of the error, sent to the frontend as `kind`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::String(_) | Error::PyRunner(PyRunnerError::PyError(_)) => "error",
+            Error::Io(_) => "io",
+            #[cfg(mobile)]
+            Error::PluginInvoke(_) => "pluginInvoke",
+            Error::Cancelled(_) => "cancelled",
+            Error::Timeout { .. } => "timeout",
+            Error::PythonException { .. } => "pythonException",
+            Error::NotRegistered(_) => "notRegistered",
+            Error::Forbidden { .. } => "forbidden",
+            Error::InvalidArgument { .. } => "invalidArgument",
+            Error::NotExposed(_) => "notExposed",
+            Error::RegistrationClosed { .. } => "registrationClosed",
+            Error::Interpreter(_) | Error::PyRunner(_) => "interpreter",
+        }
+    }
+
+    /// The message, followed by the python traceback if there is one.
+    pub(crate) fn report(&self) -> String {
+        match self {
+            Error::PythonException { traceback, .. } if !traceback.is_empty() => {
+                format!("{self}\n{traceback}")
+            }
+            _ => self.to_string(),
+        }
+    }
+
+    /// Reads the exception that `_tauri_plugin_exception` put into the error
+    /// string `text`, if there is one.
+    pub(crate) fn python_exception(context: String, text: &str) -> Option<Self> {
+        const MARKER: &str = "_TauriPluginException: ";
+        let start = text.rfind(MARKER)? + MARKER.len();
+        let json = text[start..].lines().next()?;
+        let details: ExceptionDetails = serde_json::from_str(json).ok()?;
+        Some(Error::PythonException {
+            context,
+            type_name: details.type_name,
+            message: details.message,
+            traceback: details.traceback,
+            frames: details.frames,
+        })
+    }
+}
+
+/// Sent to the frontend as an object with the `kind` of the error, a readable
+/// `message` and the details of some kinds, e.g.
+/// `{"kind": "timeout", "message": "...", "function": "fit", "timeoutMs": 1000}`.
 impl Serialize for Error {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        serializer.serialize_str(self.to_string().as_ref())
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("kind", self.kind())?;
+        map.serialize_entry("message", &self.to_string())?;
+        match self {
+            Error::Cancelled(call_id) => map.serialize_entry("callId", call_id)?,
+            Error::Timeout {
+                function,
+                timeout_ms,
+            } => {
+                map.serialize_entry("function", function)?;
+                map.serialize_entry("timeoutMs", timeout_ms)?;
+            }
+            Error::PythonException {
+                type_name,
+                message,
+                traceback,
+                frames,
+                ..
+            } => {
+                map.serialize_entry("typeName", type_name)?;
+                map.serialize_entry("exceptionMessage", message)?;
+                map.serialize_entry("traceback", traceback)?;
+                map.serialize_entry("frames", frames)?;
+            }
+            Error::NotRegistered(function) => map.serialize_entry("function", function)?,
+            Error::Forbidden { function, webview } => {
+                map.serialize_entry("function", function)?;
+                map.serialize_entry("webview", webview)?;
+            }
+            Error::InvalidArgument {
+                function,
+                parameter,
+                ..
+            } => {
+                map.serialize_entry("function", function)?;
+                map.serialize_entry("parameter", parameter)?;
+            }
+            Error::NotExposed(variable) => map.serialize_entry("variable", variable)?,
+            _ => {}
+        }
+        map.end()
     }
 }
 
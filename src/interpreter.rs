@@ -0,0 +1,175 @@
+//  Tauri Python Plugin
+//  © Copyright 2024, by Marco Mengelkoch
+//  Licensed under MIT License, see License file for more details
+//  git clone https://github.com/marcomq/tauri-plugin-python
+
+//! The python runner in use, replaced by a fresh one if it gets stuck or dies.
+//!
+//! A timed out call may leave the single worker thread of the `PyRunner` busy
+//! forever, and a panic leaves it dead; either way every later call would fail.
+//! After such an error, the runner is probed and, if it doesn't respond within
+//! `Startup::restart_after`, a new one is started and set up like at startup:
+//! `init_python`, `main.py` and the function registrations. The old worker
+//! thread is abandoned and exits once it returns. Python state held in globals
+//! is lost, so `python://restarted` is emitted for the UI to refresh.
+
+use async_py::{PyRunner, PyRunnerError};
+use std::{sync::RwLock, time::Duration};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::{
+    bridge, build_runner, config::Startup, default_timeout, describe_function, init_python,
+    install_log_handler, models::StatusResponse, python_functions, run_main_py, Error,
+    FunctionInfo, PluginState,
+};
+
+/// Event emitted after python was restarted, with the reason and the functions
+/// that were unregistered as payload.
+pub(crate) const RESTARTED_EVENT: &str = "python://restarted";
+
+pub(crate) struct Interpreter {
+    /// The runner and its generation, which counts the restarts.
+    current: RwLock<(PyRunner, u64)>,
+    /// How python was started, to start it the same way again.
+    startup: Startup,
+    restarting: tokio::sync::Mutex<()>,
+}
+
+impl Interpreter {
+    pub(crate) fn new(runner: PyRunner, startup: Startup) -> Self {
+        Self {
+            current: RwLock::new((runner, 0)),
+            startup,
+            restarting: Default::default(),
+        }
+    }
+
+    pub(crate) fn runner(&self) -> PyRunner {
+        self.current.read().unwrap().0.clone()
+    }
+
+    /// The runner along with its generation, to pass to [`recover`] on errors.
+    pub(crate) fn current(&self) -> (PyRunner, u64) {
+        self.current.read().unwrap().clone()
+    }
+
+    /// The timeout of calls that neither the call nor the function set.
+    pub(crate) fn timeout(&self) -> Option<Duration> {
+        default_timeout(self.startup.timeout)
+    }
+}
+
+/// Checks the runner of `generation` after a call failed with `err`, and
+/// restarts python in the background if it's stuck or gone.
+pub(crate) fn recover<R: Runtime>(app: &AppHandle<R>, generation: u64, err: &Error) {
+    let probe = match err {
+        Error::Timeout { .. } | Error::PyRunner(PyRunnerError::Timeout) => true,
+        Error::PyRunner(PyRunnerError::SendCommandFailed | PyRunnerError::ReceiveResultFailed) => {
+            false
+        }
+        _ => return,
+    };
+    let reason = err.to_string();
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let Some(interpreter) = app.try_state::<Interpreter>() else {
+            return;
+        };
+        let _restarting = interpreter.restarting.lock().await;
+        let (runner, current) = interpreter.current();
+        if current != generation {
+            // Someone else restarted it already.
+            return;
+        }
+        if probe {
+            // A worker that is just busy, e.g. in a C function a timed out
+            // call can't be interrupted in, gets `restart_after` to respond.
+            // Calls queued before the probe count against this.
+            let Some(restart_after) = interpreter.startup.restart_after else {
+                return;
+            };
+            if runner
+                .with_timeout(restart_after)
+                .eval("None")
+                .await
+                .is_ok()
+            {
+                return;
+            }
+        }
+        let result = restart(&app, &interpreter).await;
+        let state = app.state::<PluginState>();
+        match result {
+            Ok(unregistered) => {
+                if !unregistered.is_empty() {
+                    log::warn!(
+                        "Unregistered after restarting python, as they aren't defined anymore: {}",
+                        unregistered.join(", ")
+                    );
+                }
+                state.status.send_replace(StatusResponse::Ready);
+                let _ = app.emit(
+                    RESTARTED_EVENT,
+                    serde_json::json!({ "reason": reason, "unregistered": unregistered }),
+                );
+            }
+            Err(err) => {
+                log::error!("Error restarting python: {err}");
+                state.status.send_replace(StatusResponse::Failed {
+                    error: err.report(),
+                });
+            }
+        }
+    });
+}
+
+/// Replaces the runner by a fresh one. Returns the registered functions that
+/// the fresh runner doesn't define, e.g. as they were only defined with
+/// `run_python`; they are unregistered.
+async fn restart<R: Runtime>(
+    app: &AppHandle<R>,
+    interpreter: &Interpreter,
+) -> crate::Result<Vec<String>> {
+    let startup = &interpreter.startup;
+    let runner = build_runner(startup.timeout);
+    init_python(app, &runner, startup).await?;
+    if let Some(main_py) = &startup.main_py {
+        run_main_py(&runner, main_py).await?;
+    }
+    install_log_handler(&runner, startup).await?;
+    bridge::drain_outbox(app, &runner).await;
+    // Makes the `@tauri.command` functions of main.py available by name again.
+    python_functions(&runner).await?;
+    // Registrations are taken from the plugin, as they may also have come from
+    // the frontend. Only the per-function settings are kept.
+    let state = app.state::<PluginState>();
+    let names: Vec<String> = state.function_map.read().unwrap().keys().cloned().collect();
+    let mut described = Vec::new();
+    let mut unregistered = Vec::new();
+    for name in names {
+        match describe_function(&runner, &name).await {
+            Ok(info) => described.push((name, info)),
+            Err(_) => unregistered.push(name),
+        }
+    }
+    // All at once, so calls never see a partly updated registry. Functions
+    // unregistered meanwhile stay unregistered.
+    {
+        let mut function_map = state.function_map.write().unwrap();
+        for (name, info) in described {
+            if let Some(registered) = function_map.get_mut(&name) {
+                *registered = FunctionInfo {
+                    timeout: registered.timeout,
+                    access: registered.access.clone(),
+                    ..info
+                };
+            }
+        }
+        for name in &unregistered {
+            function_map.remove(name);
+        }
+    }
+    let mut current = interpreter.current.write().unwrap();
+    *current = (runner, current.1 + 1);
+    Ok(unregistered)
+}
@@ -3,28 +3,46 @@
 //  Licensed under MIT License, see License file for more details
 //  git clone https://github.com/marcomq/tauri-plugin-python
 
-use tauri::{
-    path::BaseDirectory,
-    plugin::{Builder, TauriPlugin},
-    AppHandle, Manager, Runtime,
-};
+use tauri::{ipc::Channel, plugin::TauriPlugin, AppHandle, Manager, Runtime, Webview};
 
 #[cfg(desktop)]
 mod desktop;
 #[cfg(mobile)]
 mod mobile;
 
+mod arguments;
+mod bindings;
+mod bridge;
+mod builder;
+mod calls;
 mod commands;
+mod config;
 mod error;
+mod interpreter;
 mod models;
+mod output;
+mod scope;
+mod streams;
 use async_py::{self, PyRunner};
 
+pub use builder::Builder;
+use calls::CallGuard;
+use config::Startup;
+pub use config::{Config, InitErrorPolicy, LogLevel, RegistrationPolicy};
 pub use error::{Error, Result};
+use interpreter::Interpreter;
 use models::*;
+use scope::FunctionScope;
+use serde::Deserialize;
+use serde_json::Value;
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
+    future::Future,
     path::PathBuf,
-    sync::{atomic::AtomicBool, Mutex},
+    sync::{
+        atomic::{AtomicBool, AtomicU64},
+        Arc, Mutex, RwLock,
+    },
     time::Duration,
 };
 
@@ -32,10 +50,12 @@ use std::{
 /// (e.g. a blocking `print()` on a hidden-console Windows build, or a network
 /// call whose own timeout never fires) cannot hang every later call forever.
 /// Generous on purpose so it won't interfere with legitimately long work.
-/// Override via the `TAURI_PLUGIN_PYTHON_TIMEOUT_SECS` env var (`0` disables it).
+/// Override via `timeoutSecs` in the plugin config or the
+/// `TAURI_PLUGIN_PYTHON_TIMEOUT_SECS` env var (`0` disables it).
 const DEFAULT_TIMEOUT_SECS: u64 = 300;
 
-/// Python executed once at startup, before `main.py`, to make stdio safe.
+/// Python executed once at startup, before `main.py`, to make stdio safe and
+/// forward it to the plugin.
 ///
 /// On a Windows release build the console is hidden (`windows_subsystem =
 /// "windows"`), so the standard handles are missing/invalid and a bare
@@ -43,47 +63,99 @@ const DEFAULT_TIMEOUT_SECS: u64 = 300;
 /// wraps `sys.stdout`/`sys.stderr` so writes can never crash the app. It cannot
 /// un-block a write that hangs on a full, unread pipe - the call timeout is the
 /// backstop for that - but it removes the common crash-on-stdio failure mode.
+/// Complete lines are also passed to the `tauri` module once it's installed,
+/// which hands them to [`output::Output`].
 const PY_STDIO_GUARD: &str = r#"import sys
+import threading
 
-class _TauriSafeStream:
-    def __init__(self, real):
-        self._real = real
+class _TauriStream:
+    def __init__(self, name, real):
+        self._tauri_name = name
+        self._tauri_real = real
+        self._pending = ""
+        self._lock = threading.Lock()
     def write(self, data):
         try:
-            if self._real is not None:
-                return self._real.write(data)
+            if self._tauri_real is not None:
+                self._tauri_real.write(data)
         except Exception:
             pass
-        return 0
+        with self._lock:
+            *lines, self._pending = (self._pending + data).split("\n")
+        self._forward(lines)
+        return len(data)
     def flush(self):
+        with self._lock:
+            lines = [self._pending] if self._pending else []
+            self._pending = ""
+        self._forward(lines)
         try:
-            if self._real is not None:
-                self._real.flush()
+            if self._tauri_real is not None:
+                self._tauri_real.flush()
         except Exception:
             pass
+    def _forward(self, lines):
+        module = globals().get("tauri")
+        for line in lines:
+            try:
+                module._output(self._tauri_name, line)
+            except Exception:
+                pass
     def isatty(self):
         try:
-            return bool(self._real is not None and self._real.isatty())
+            return bool(self._tauri_real is not None and self._tauri_real.isatty())
         except Exception:
             return False
     def __getattr__(self, name):
-        return getattr(self._real, name)
+        return getattr(self._tauri_real, name)
+
+def _tauri_install_stream(name):
+    current = getattr(sys, name, None)
+    # Replaces the stream installed for an earlier runner instead of wrapping it.
+    setattr(sys, name, _TauriStream(name, getattr(current, "_tauri_real", current)))
 
-sys.stdout = _TauriSafeStream(getattr(sys, "stdout", None))
-sys.stderr = _TauriSafeStream(getattr(sys, "stderr", None))
+_tauri_install_stream("stdout")
+_tauri_install_stream("stderr")
 "#;
 
-/// Builds the shared [`PyRunner`], applying the default per-call timeout unless
-/// the `TAURI_PLUGIN_PYTHON_TIMEOUT_SECS` env var overrides it (`0` = no timeout).
-fn build_runner() -> PyRunner {
-    let runner = PyRunner::new();
+/// Python helpers the plugin relies on (calling with keyword arguments,
+/// inspecting signatures, running `async def` functions), run once at startup
+/// right after `PY_STDIO_GUARD`.
+const PY_PLUGIN_HELPERS: &str = include_str!("plugin_helpers.py");
+
+/// Source of the built-in `tauri` python module, installed before `main.py` runs.
+const PY_TAURI_MODULE: &str = include_str!("tauri_module.py");
+
+/// Number of items pulled from a generator per round trip to the worker in
+/// `call_function_stream`. The next batch is only pulled after the previous one
+/// was handled by the receiver, so a fast generator can't run ahead of the
+/// webview; see [`streams`].
+const STREAM_BATCH_SIZE: usize = 16;
+
+/// Upper bound for the delay between two polls of a running `async def` call.
+/// Polling starts at 1ms and backs off to this, so short coroutines return
+/// quickly while long ones only cost a cheap check on the worker now and then.
+const ASYNC_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// The default timeout of calls: the configured `timeout`, unless the
+/// `TAURI_PLUGIN_PYTHON_TIMEOUT_SECS` env var overrides it (`0` = no timeout).
+fn default_timeout(timeout: Option<Duration>) -> Option<Duration> {
     match std::env::var("TAURI_PLUGIN_PYTHON_TIMEOUT_SECS")
         .ok()
         .and_then(|v| v.trim().parse::<u64>().ok())
     {
-        Some(0) => runner,
-        Some(secs) => runner.with_timeout(Duration::from_secs(secs)),
-        None => runner.with_timeout(Duration::from_secs(DEFAULT_TIMEOUT_SECS)),
+        Some(0) => None,
+        Some(secs) => Some(Duration::from_secs(secs)),
+        None => timeout,
+    }
+}
+
+/// Builds the shared [`PyRunner`], applying the [`default_timeout`] of calls.
+fn build_runner(timeout: Option<Duration>) -> PyRunner {
+    let runner = PyRunner::new();
+    match default_timeout(timeout) {
+        Some(timeout) => runner.with_timeout(timeout),
+        None => runner,
     }
 }
 
@@ -92,10 +164,258 @@ use desktop::Python;
 #[cfg(mobile)]
 use mobile::Python;
 
+/// A Rust function callable from python via `tauri.call_rust`, see
+/// [`Builder::rust_handler`].
+type RustHandler = dyn Fn(Vec<Value>) -> std::result::Result<Value, String> + Send + Sync;
+
 #[derive(Default)]
 struct PluginState {
     init_blocked: AtomicBool,
-    function_map: Mutex<HashSet<String>>,
+    /// The registered functions. Every change is made with a single write, so
+    /// calls see a registry either before or after it.
+    function_map: RwLock<HashMap<String, FunctionInfo>>,
+    /// Variables the frontend may read with `read_variable`.
+    variables: Mutex<HashSet<String>>,
+    rust_handlers: HashMap<String, Arc<RustHandler>>,
+    calls: calls::Calls,
+    streams: streams::Streams,
+    /// The id the `tauri` module of the current runner passes to the bridge.
+    bridge_id: AtomicU64,
+    registration: RegistrationPolicy,
+    status: tokio::sync::watch::Sender<StatusResponse>,
+    /// How long calls wait for python to finish starting.
+    ready_timeout: Duration,
+    output: output::Output,
+}
+
+/// What the plugin knows about a registered function, as reported by
+/// `_tauri_plugin_describe` at registration time.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct FunctionInfo {
+    /// `None` if the signature couldn't be inspected.
+    parameters: Option<Vec<ParameterInfo>>,
+    /// `async def` functions are driven on the plugin's asyncio event loop.
+    is_async: bool,
+    /// Generators (sync or async) are meant for `call_function_stream`.
+    is_generator: bool,
+    /// The return type hint, if there is one.
+    returns: Option<TypeHint>,
+    /// The docstring.
+    doc: Option<String>,
+    /// Default timeout of calls, set at registration.
+    #[serde(skip)]
+    timeout: Option<Duration>,
+    #[serde(skip)]
+    access: Access,
+}
+
+/// Which webviews may call a function, set at registration. Each list that is
+/// set restricts the callers; without any, every webview may call it.
+#[derive(Debug, Clone, Default)]
+struct Access {
+    /// Labels of the allowed windows or webviews.
+    windows: Option<Vec<String>>,
+    /// Allowed origins of the webview URL.
+    origins: Option<Vec<String>>,
+}
+
+impl Access {
+    /// Whether a webview with one of `labels` (its own and its window's) and
+    /// `origin` may call the function.
+    fn allows(&self, labels: &[&str], origin: Option<&str>) -> bool {
+        let window_allowed = self.windows.as_ref().is_none_or(|windows| {
+            windows
+                .iter()
+                .any(|window| labels.contains(&window.as_str()))
+        });
+        let origin_allowed = self.origins.as_ref().is_none_or(|origins| {
+            origin.is_some_and(|origin| origins.iter().any(|allowed| allowed == origin))
+        });
+        window_allowed && origin_allowed
+    }
+}
+
+/// Rejects calls of `function_name` the scopes of the caller's capabilities
+/// don't allow: it must not be denied and, if any scope allows functions, be
+/// one of them. Without scopes, e.g. with just `allow-call-function`, every
+/// registered function may be called.
+fn check_scope(
+    function_name: &str,
+    webview: &str,
+    allows: &[&FunctionScope],
+    denies: &[&FunctionScope],
+) -> crate::Result<()> {
+    let listed = |scopes: &[&FunctionScope]| {
+        scopes
+            .iter()
+            .any(|scope| scope.functions.iter().any(|name| name == function_name))
+    };
+    if listed(denies) || !(allows.is_empty() || listed(allows)) {
+        return Err(Error::Forbidden {
+            function: function_name.into(),
+            webview: webview.into(),
+        });
+    }
+    Ok(())
+}
+
+/// Rejects calls of `function_name` from a `webview` its registration doesn't
+/// allow. Functions that aren't registered are left to the call to report.
+fn check_access<R: Runtime>(
+    app: &AppHandle<R>,
+    webview: &Webview<R>,
+    function_name: &str,
+) -> crate::Result<()> {
+    let state = app.state::<PluginState>();
+    let Some(access) = state
+        .function_map
+        .read()
+        .unwrap()
+        .get(function_name)
+        .map(|info| info.access.clone())
+    else {
+        return Ok(());
+    };
+    let origin = webview
+        .url()
+        .ok()
+        .map(|url| url.origin().ascii_serialization());
+    let window = webview.window();
+    if access.allows(&[webview.label(), window.label()], origin.as_deref()) {
+        Ok(())
+    } else {
+        Err(Error::Forbidden {
+            function: function_name.into(),
+            webview: webview.label().into(),
+        })
+    }
+}
+
+/// Rejects reads of `name` by the frontend, unless it was exposed with
+/// `_tauri_plugin_variables` or [`Builder::variable`].
+fn check_variable<R: Runtime>(app: &AppHandle<R>, name: &str) -> crate::Result<()> {
+    let state = app.state::<PluginState>();
+    if state.variables.lock().unwrap().contains(name) {
+        Ok(())
+    } else {
+        Err(Error::NotExposed(name.into()))
+    }
+}
+
+/// Waits until python finished starting, for at most the `ready_timeout`, and
+/// returns whether that worked.
+async fn started(state: &PluginState) -> crate::Result<StatusResponse> {
+    let mut status = state.status.subscribe();
+    let started = status.wait_for(|status| *status != StatusResponse::Starting);
+    match tokio::time::timeout(state.ready_timeout, started).await {
+        Ok(Ok(status)) => Ok(status.clone()),
+        _ => Err(Error::String(format!(
+            "Python is still starting after {} s",
+            state.ready_timeout.as_secs()
+        ))),
+    }
+}
+
+/// Like [`started`], but fails unless python is ready for calls.
+async fn ready(state: &PluginState) -> crate::Result<()> {
+    match started(state).await? {
+        StatusResponse::Failed { error } => Err(Error::String(format!(
+            "Python failed to initialize: {error}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Rejects registering or unregistering functions once the registration
+/// policy closed it. `operation` is `register` or `unregister`.
+fn check_registration_open(state: &PluginState, operation: &'static str) -> crate::Result<()> {
+    if !state
+        .init_blocked
+        .load(std::sync::atomic::Ordering::Relaxed)
+    {
+        return Ok(());
+    }
+    let reason = match state.registration {
+        RegistrationPolicy::FreezeOnFirstCall => "after function called",
+        RegistrationPolicy::FreezeAfterSetup => "after setup",
+        RegistrationPolicy::Never | RegistrationPolicy::RustOnly => return Ok(()),
+    };
+    Err(Error::RegistrationClosed { operation, reason })
+}
+
+/// Cancels the call `call_id` on behalf of `webview`, see [`calls::Calls::cancel`].
+fn cancel_running_call(
+    state: &PluginState,
+    call_id: &str,
+    webview: Option<&str>,
+) -> crate::Result<StringResponse> {
+    calls::check_call_id(call_id)?;
+    if !state.calls.cancel(call_id, webview) {
+        return Err(Error::String(format!(
+            "No running call with id '{call_id}'"
+        )));
+    }
+    Ok(StringResponse { value: "Ok".into() })
+}
+
+/// Returns the registered function `function_name`. With
+/// [`RegistrationPolicy::FreezeOnFirstCall`], this closes registration.
+fn lookup_function(state: &PluginState, function_name: &str) -> crate::Result<FunctionInfo> {
+    if state.registration == RegistrationPolicy::FreezeOnFirstCall {
+        state
+            .init_blocked
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+    state
+        .function_map
+        .read()
+        .unwrap()
+        .get(function_name)
+        .cloned()
+        .ok_or_else(|| Error::NotRegistered(function_name.into()))
+}
+
+/// The runner to make a call with: with an explicit timeout, this replaces the
+/// runner's default one, which may well be shorter.
+fn runner_for(runner: PyRunner, timeout: Option<Duration>) -> PyRunner {
+    match timeout {
+        Some(timeout) => runner.with_timeout(timeout),
+        None => runner,
+    }
+}
+
+/// What `_tauri_plugin_describe` reports about the function `name`. Fails if
+/// there is no such function.
+async fn describe_function(runner: &PyRunner, name: &str) -> crate::Result<FunctionInfo> {
+    let info = py_context(
+        runner
+            .call_function("_tauri_plugin_describe", vec![name.into()])
+            .await,
+        || format!("Error describing Python function '{name}'"),
+    )?;
+    serde_json::from_value(info)
+        .map_err(|err| Error::String(format!("Invalid description of '{name}': {err}")))
+}
+
+/// Polls a task scheduled on the plugin's event loop (see `_tauri_plugin_schedule`)
+/// until it's done. The worker thread is only busy for the polls, so other
+/// calls - including other coroutines - run in between.
+async fn await_python_task(
+    runner: &PyRunner,
+    task_id: Value,
+) -> std::result::Result<Value, async_py::PyRunnerError> {
+    let mut delay = Duration::from_millis(1);
+    loop {
+        let mut poll = runner
+            .call_function("_tauri_plugin_poll_task", vec![task_id.clone()])
+            .await?;
+        if poll["done"] == true {
+            return Ok(poll["value"].take());
+        }
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(ASYNC_POLL_INTERVAL);
+    }
 }
 
 /// Prepends human-readable context to a failing Python operation and, in debug
@@ -103,28 +423,227 @@ struct PluginState {
 /// carried in the underlying error - to stderr so it is visible in the dev
 /// console. The original error message is preserved in the returned error, so it
 /// also still reaches the frontend. In release builds nothing is logged.
+/// Exceptions of python code called through the plugin helpers keep their
+/// details; other structured errors, like cancellations and timeouts, are
+/// passed through as they are, so the frontend can tell them apart.
 fn py_context<T, E: Into<Error>>(
     result: std::result::Result<T, E>,
     context: impl FnOnce() -> String,
 ) -> crate::Result<T> {
     result.map_err(|err| {
-        let msg = format!("{}: {}", context(), err.into());
+        let err = match err.into() {
+            Error::PyRunner(async_py::PyRunnerError::PyError(text)) => {
+                let context = context();
+                Error::python_exception(context.clone(), &text).unwrap_or_else(|| {
+                    Error::String(format!(
+                        "{context}: {}",
+                        async_py::PyRunnerError::PyError(text)
+                    ))
+                })
+            }
+            Error::PyRunner(err) => Error::Interpreter(format!("{}: {err}", context())),
+            Error::String(msg) => Error::String(format!("{}: Error: {msg}", context())),
+            Error::Io(err) => Error::String(format!("{}: {err}", context())),
+            err => return err,
+        };
         #[cfg(debug_assertions)]
-        eprintln!("[tauri-plugin-python] {msg}");
-        Error::String(msg)
+        eprintln!("[tauri-plugin-python] {}", err.report());
+        err
     })
 }
 
+/// Awaits `future` unless the call is cancelled or `timeout` expires first.
+/// An expired timeout of the runner itself is reported the same way.
+async fn supervise<T>(
+    function_name: &str,
+    call: Option<&mut CallGuard<'_>>,
+    timeout: Option<Duration>,
+    future: impl Future<Output = std::result::Result<T, async_py::PyRunnerError>>,
+) -> crate::Result<T> {
+    let bounded = async {
+        let Some(timeout) = timeout else {
+            return Ok(future.await?);
+        };
+        match tokio::time::timeout(timeout, future).await {
+            Ok(Err(async_py::PyRunnerError::Timeout)) | Err(_) => Err(Error::Timeout {
+                function: function_name.into(),
+                timeout_ms: timeout.as_millis() as u64,
+            }),
+            Ok(result) => Ok(result?),
+        }
+    };
+    match call {
+        Some(call) => call.unless_cancelled(bounded).await?,
+        None => bounded.await,
+    }
+}
+
+/// Streams the items yielded by a generator to `channel`, see
+/// [`PythonExt::call_function_stream`]. With `flow_control`, every batch waits
+/// for the receiver to acknowledge it (see [`streams`]), as the frontend only
+/// gets the items queued.
+async fn stream_function<R: Runtime>(
+    app: &AppHandle<R>,
+    payload: RunRequest,
+    channel: Channel<StreamMessage>,
+    flow_control: bool,
+) -> crate::Result<()> {
+    let function_name = payload.function_name.clone();
+    let state = app.state::<PluginState>().inner();
+    ready(state).await?;
+    let info = lookup_function(state, &function_name)?;
+    if !info.is_generator {
+        // Plain functions stream their single result.
+        let result = app.call_function_json(payload).await;
+        return match result {
+            Ok(response) => {
+                channel.send(StreamMessage::Item(response.value))?;
+                channel.send(StreamMessage::Done)?;
+                Ok(())
+            }
+            Err(err) => {
+                channel.send(StreamMessage::Error(err.to_string()))?;
+                Err(err)
+            }
+        };
+    }
+    let kwargs = payload.kwargs.unwrap_or_default();
+    if let Some(parameters) = &info.parameters {
+        arguments::check(&function_name, parameters, &payload.args, &kwargs)?;
+    }
+    let mut call = payload
+        .call_id
+        .as_deref()
+        .map(|call_id| state.calls.start(call_id, payload.webview.as_deref()))
+        .transpose()?;
+    let mut acks = flow_control.then(|| state.streams.start(channel.id()));
+    let interpreter = app.state::<Interpreter>();
+    // Bounds the wait for each batch of items and its acknowledgement, not the
+    // whole stream.
+    let timeout = payload
+        .timeout_ms
+        .map(Duration::from_millis)
+        .or(info.timeout)
+        .or_else(|| interpreter.timeout());
+    let (runner, generation) = interpreter.current();
+    let runner = &runner_for(runner, timeout);
+    let result: crate::Result<bool> = async {
+        let start = runner.call_function(
+            "_tauri_plugin_start_stream",
+            vec![
+                function_name.clone().into(),
+                payload.args.into(),
+                kwargs.into(),
+            ],
+        );
+        let stream_id = supervise(&function_name, None, timeout, start).await?;
+        // Stops the generator; one that is mid-batch is stopped once it's idle.
+        let close = || runner.call_function("_tauri_plugin_close_stream", vec![stream_id.clone()]);
+        let mut batches = 0;
+        loop {
+            let batch = supervise(&function_name, call.as_mut(), timeout, async {
+                let mut batch = runner
+                    .call_function(
+                        "_tauri_plugin_next_items",
+                        vec![stream_id.clone(), STREAM_BATCH_SIZE.into()],
+                    )
+                    .await?;
+                if let Some(task_id) = batch.get_mut("task") {
+                    batch = await_python_task(runner, task_id.take()).await?;
+                }
+                Ok::<_, async_py::PyRunnerError>(batch)
+            })
+            .await;
+            bridge::drain_outbox(app, runner).await;
+            let mut batch = match batch {
+                Err(err @ (Error::Cancelled(_) | Error::Timeout { .. })) => {
+                    let _ = close().await;
+                    return Err(err);
+                }
+                batch => batch?,
+            };
+            let items = match batch["items"].take() {
+                Value::Array(items) => items,
+                _ => Vec::new(),
+            };
+            for item in items {
+                if channel.send(StreamMessage::Item(item)).is_err() {
+                    // The receiving side is gone.
+                    let _ = close().await;
+                    return Ok(false);
+                }
+            }
+            if batch["done"] == true {
+                return Ok(true);
+            }
+            let Some(acks) = acks.as_mut() else {
+                continue;
+            };
+            batches += 1;
+            if channel.send(StreamMessage::Batch(batches)).is_err() {
+                let _ = close().await;
+                return Ok(false);
+            }
+            let acked = supervise(&function_name, call.as_mut(), timeout, async {
+                acks.acked(batches).await;
+                Ok::<_, async_py::PyRunnerError>(())
+            })
+            .await;
+            if let Err(err) = acked {
+                let _ = close().await;
+                return Err(err);
+            }
+        }
+    }
+    .await;
+    if let Err(err) = &result {
+        interpreter::recover(app, generation, err);
+    }
+    match py_context(result, || {
+        format!("Error streaming Python function '{function_name}'")
+    }) {
+        Ok(true) => Ok(channel.send(StreamMessage::Done)?),
+        Ok(false) => Ok(()),
+        Err(err) => {
+            let _ = channel.send(StreamMessage::Error(err.to_string()));
+            Err(err)
+        }
+    }
+}
+
 /// Extensions to [`tauri::App`], [`tauri::AppHandle`] and [`tauri::Window`] to access the python APIs.
 
 #[async_trait::async_trait]
 pub trait PythonExt<R: Runtime> {
     fn python(&self) -> &Python<R>;
-    fn runner(&self) -> &PyRunner;
+    /// The current runner. It's replaced if python has to be restarted, so
+    /// don't hold on to it for longer than needed.
+    fn runner(&self) -> PyRunner;
     async fn run_python(&self, payload: StringRequest) -> crate::Result<StringResponse>;
     async fn register_function(&self, payload: RegisterRequest) -> crate::Result<StringResponse>;
+    /// Removes the function `payload.value`, so it can't be called anymore.
+    /// Calls that already started aren't affected.
+    async fn unregister_function(&self, payload: StringRequest) -> crate::Result<StringResponse>;
     async fn call_function(&self, payload: RunRequest) -> crate::Result<StringResponse>;
+    async fn call_function_json(&self, payload: RunRequest) -> crate::Result<ValueResponse>;
+    async fn call_function_stream(
+        &self,
+        payload: RunRequest,
+        channel: Channel<StreamMessage>,
+    ) -> crate::Result<()>;
+    async fn cancel_call(&self, payload: StringRequest) -> crate::Result<StringResponse>;
     async fn read_variable(&self, payload: StringRequest) -> crate::Result<StringResponse>;
+    async fn get_status(&self) -> crate::Result<StatusResponse>;
+    /// Waits until python finished starting, see `Config::ready_timeout_secs`.
+    async fn wait_ready(&self) -> crate::Result<StatusResponse>;
+    /// The latest lines python printed, see `Config::output_buffer_lines`.
+    async fn get_output(&self) -> crate::Result<Vec<OutputLine>>;
+    /// The registered functions with their signatures and docstrings, sorted
+    /// by name.
+    async fn list_functions(&self) -> crate::Result<Vec<FunctionDescription>>;
+    /// A TypeScript module with a typed wrapper of every registered function,
+    /// see [`Builder::typescript_bindings`].
+    fn typescript_bindings(&self) -> String;
 }
 
 #[async_trait::async_trait]
@@ -132,23 +651,28 @@ impl<R: Runtime, T: Manager<R> + Sync> crate::PythonExt<R> for T {
     fn python(&self) -> &Python<R> {
         self.state::<Python<R>>().inner()
     }
-    fn runner(&self) -> &PyRunner {
-        self.state::<PyRunner>().inner()
+    fn runner(&self) -> PyRunner {
+        self.state::<Interpreter>().runner()
     }
     async fn run_python(&self, payload: StringRequest) -> crate::Result<StringResponse> {
-        py_context(self.runner().run(&payload.value).await, || {
-            "Error running Python code (runPython)".into()
-        })?;
+        let (runner, generation) = self.state::<Interpreter>().current();
+        let result = runner.run(&payload.value).await.map_err(Error::from);
+        bridge::drain_outbox(self.app_handle(), &runner).await;
+        if let Err(err) = &result {
+            interpreter::recover(self.app_handle(), generation, err);
+        }
+        py_context(result, || "Error running Python code (runPython)".into())?;
         Ok(StringResponse { value: "Ok".into() })
     }
 
     async fn register_function(&self, payload: RegisterRequest) -> crate::Result<StringResponse> {
         let state = self.state::<PluginState>().inner();
-        if state
-            .init_blocked
-            .load(std::sync::atomic::Ordering::Relaxed)
-        {
-            return Err("Cannot register after function called".into());
+        check_registration_open(state, "register")?;
+        if !is_python_name(&payload.python_function_call) {
+            return Err(Error::String(format!(
+                "Cannot register '{}': not a python function name",
+                payload.python_function_call
+            )));
         }
         let _tmp = py_context(
             self.runner()
@@ -161,63 +685,50 @@ impl<R: Runtime, T: Manager<R> + Sync> crate::PythonExt<R> for T {
                 )
             },
         )?;
-        if let Some(num_args) = payload.number_of_args {
-            // Validate the argument count via `inspect.signature`, but only
-            // *reject* the registration on an actual mismatch. If the check
-            // itself can't run - e.g. the RustPython backend can't import
-            // `inspect` - the import failure is swallowed in Python so the call
-            // succeeds and registration proceeds without validation, rather
-            // than failing on an unrelated error.
-            let py_analyze_sig = format!(
-                r#"
-try:
-    from inspect import signature
-    _tauri_param_count = len(signature({0}).parameters)
-except Exception:
-    _tauri_param_count = None
-if _tauri_param_count is not None and _tauri_param_count != {1}:
-    raise Exception("Function parameters don't match in 'registerFunction'")
-"#,
-                &payload.python_function_call, num_args
-            );
-            self.runner().run(&py_analyze_sig).await.map_err(|_| {
-                Error::String(format!(
+        // Without a description, e.g. if python can't provide one, the
+        // function is registered with the defaults.
+        let info = describe_function(&self.runner(), &payload.python_function_call)
+            .await
+            .unwrap_or_default();
+        // Only an actual mismatch rejects the registration. If the signature
+        // can't be inspected - e.g. the RustPython backend can't import
+        // `inspect` - registration proceeds without validation.
+        if let (Some(num_args), Some(parameters)) = (payload.number_of_args, &info.parameters) {
+            if parameters.len() != usize::from(num_args) {
+                return Err(Error::String(format!(
                     "Function parameters don't match signature of {}.",
                     payload.python_function_call
-                ))
-            })?;
+                )));
+            }
+        }
+        let info = FunctionInfo {
+            timeout: payload.timeout_ms.map(Duration::from_millis),
+            access: Access {
+                windows: payload.windows,
+                origins: payload.origins,
+            },
+            ..info
         };
-        state
-            .function_map
-            .lock()
-            .unwrap()
-            .insert(payload.python_function_call.clone());
+        // Checked again with the lock held, as a call may have closed the
+        // registration while python described the function.
+        let mut function_map = state.function_map.write().unwrap();
+        check_registration_open(state, "register")?;
+        function_map.insert(payload.python_function_call, info);
         Ok(StringResponse { value: "Ok".into() })
     }
 
-    async fn call_function(&self, payload: RunRequest) -> crate::Result<StringResponse> {
+    async fn unregister_function(&self, payload: StringRequest) -> crate::Result<StringResponse> {
         let state = self.state::<PluginState>().inner();
-        state
-            .init_blocked
-            .store(true, std::sync::atomic::Ordering::Relaxed);
-        let function_name = payload.function_name;
-        if state
-            .function_map
-            .lock()
-            .unwrap()
-            .get(&function_name)
-            .is_none()
-        {
-            return Err(Error::String(format!(
-                "Function {function_name} has not been registered yet"
-            )));
+        let mut function_map = state.function_map.write().unwrap();
+        check_registration_open(state, "unregister")?;
+        match function_map.remove(&payload.value) {
+            Some(_) => Ok(StringResponse { value: "Ok".into() }),
+            None => Err(Error::NotRegistered(payload.value)),
         }
-        let py_res = py_context(
-            self.runner()
-                .call_function(&function_name, payload.args)
-                .await,
-            || format!("Error calling Python function '{function_name}'"),
-        )?;
+    }
+
+    async fn call_function(&self, payload: RunRequest) -> crate::Result<StringResponse> {
+        let py_res = self.call_function_json(payload).await?.value;
         let value = match py_res.as_str() {
             Some(s) => s.to_string(),
             None => py_res.to_string(),
@@ -225,6 +736,109 @@ if _tauri_param_count is not None and _tauri_param_count != {1}:
         Ok(StringResponse { value })
     }
 
+    async fn call_function_json(&self, payload: RunRequest) -> crate::Result<ValueResponse> {
+        let function_name = payload.function_name;
+        let state = self.state::<PluginState>().inner();
+        ready(state).await?;
+        let info = lookup_function(state, &function_name)?;
+        if info.is_generator {
+            return Err(Error::String(format!(
+                "Function '{function_name}' is a generator, use call_function_stream to call it"
+            )));
+        }
+        let kwargs = payload.kwargs.unwrap_or_default();
+        if let Some(parameters) = &info.parameters {
+            arguments::check(&function_name, parameters, &payload.args, &kwargs)?;
+        }
+        let mut call = payload
+            .call_id
+            .as_deref()
+            .map(|call_id| state.calls.start(call_id, payload.webview.as_deref()))
+            .transpose()?;
+        let interpreter = self.state::<Interpreter>();
+        // If neither the call nor the function sets one, the default timeout
+        // is applied here too, as the runner's only bounds each poll of an
+        // `async def`.
+        let timeout = payload
+            .timeout_ms
+            .map(Duration::from_millis)
+            .or(info.timeout)
+            .or_else(|| interpreter.timeout());
+        let (runner, generation) = interpreter.current();
+        let runner = runner_for(runner, timeout);
+        let result = if info.is_async {
+            let start = runner.call_function(
+                "_tauri_plugin_start_task",
+                vec![
+                    function_name.clone().into(),
+                    payload.args.into(),
+                    kwargs.into(),
+                ],
+            );
+            match supervise(&function_name, None, timeout, start).await {
+                Ok(task_id) => {
+                    let result = supervise(
+                        &function_name,
+                        call.as_mut(),
+                        timeout,
+                        await_python_task(&runner, task_id.clone()),
+                    )
+                    .await;
+                    if matches!(result, Err(Error::Cancelled(_) | Error::Timeout { .. })) {
+                        let _ = runner
+                            .call_function("_tauri_plugin_cancel_task", vec![task_id])
+                            .await;
+                    }
+                    result
+                }
+                Err(err) => Err(err),
+            }
+        } else {
+            // Goes through python, so it can be interrupted. Calls without an
+            // id get one, as python code that timed out is interrupted too.
+            let call = call.get_or_insert_with(|| state.calls.start_unnamed());
+            let args = vec![
+                call.call_id().into(),
+                function_name.clone().into(),
+                payload.args.into(),
+                kwargs.into(),
+            ];
+            let result = supervise(
+                &function_name,
+                Some(&mut *call),
+                timeout,
+                runner.call_function("_tauri_plugin_run_call", args),
+            )
+            .await;
+            if matches!(result, Err(Error::Timeout { .. })) {
+                state.calls.cancel(call.call_id(), None);
+            }
+            result
+        };
+        bridge::drain_outbox(self.app_handle(), &runner).await;
+        if let Err(err) = &result {
+            interpreter::recover(self.app_handle(), generation, err);
+        }
+        let py_res = py_context(result, || {
+            format!("Error calling Python function '{function_name}'")
+        })?;
+        Ok(ValueResponse { value: py_res })
+    }
+
+    async fn call_function_stream(
+        &self,
+        payload: RunRequest,
+        channel: Channel<StreamMessage>,
+    ) -> crate::Result<()> {
+        // The closure of a channel created in Rust runs right away, so the
+        // stream can't run ahead of it.
+        stream_function(self.app_handle(), payload, channel, false).await
+    }
+
+    async fn cancel_call(&self, payload: StringRequest) -> crate::Result<StringResponse> {
+        cancel_running_call(self.state::<PluginState>().inner(), &payload.value, None)
+    }
+
     async fn read_variable(&self, payload: StringRequest) -> crate::Result<StringResponse> {
         let py_res = py_context(self.runner().read_variable(&payload.value).await, || {
             format!("Error reading Python variable '{}'", payload.value)
@@ -233,16 +847,49 @@ if _tauri_param_count is not None and _tauri_param_count != {1}:
             value: py_res.to_string(),
         })
     }
-}
 
-fn get_resource_dir<R: Runtime>(app: &AppHandle<R>) -> PathBuf {
-    app.path()
-        .resolve("src-python", BaseDirectory::Resource)
-        .unwrap_or_default()
-}
+    async fn get_status(&self) -> crate::Result<StatusResponse> {
+        Ok(self.state::<PluginState>().status.borrow().clone())
+    }
+
+    async fn wait_ready(&self) -> crate::Result<StatusResponse> {
+        started(self.state::<PluginState>().inner()).await
+    }
+
+    async fn get_output(&self) -> crate::Result<Vec<OutputLine>> {
+        Ok(self.state::<PluginState>().output.lines())
+    }
 
-fn get_src_python_dir() -> PathBuf {
-    std::env::current_dir().unwrap().join("src-python")
+    async fn list_functions(&self) -> crate::Result<Vec<FunctionDescription>> {
+        let state = self.state::<PluginState>();
+        let mut functions: Vec<FunctionDescription> = state
+            .function_map
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, info)| FunctionDescription {
+                name: name.clone(),
+                parameters: info.parameters.clone(),
+                returns: info.returns.clone(),
+                doc: info.doc.clone(),
+                is_async: info.is_async,
+                is_generator: info.is_generator,
+                timeout_ms: info.timeout.map(|timeout| timeout.as_millis() as u64),
+            })
+            .collect();
+        functions.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(functions)
+    }
+
+    fn typescript_bindings(&self) -> String {
+        let functions = self
+            .state::<PluginState>()
+            .function_map
+            .read()
+            .unwrap()
+            .clone();
+        bindings::typescript(&functions)
+    }
 }
 
 /// Initializes the plugin with functions
@@ -252,116 +899,186 @@ pub fn init<R: Runtime>() -> TauriPlugin<R> {
 
 fn cleanup_path_for_python(path: &PathBuf) -> String {
     dunce::canonicalize(path)
-        .unwrap()
+        .unwrap_or_else(|_| path.clone())
         .to_string_lossy()
         .replace("\\", "/")
 }
 
-fn print_path_for_python(path: &PathBuf) -> String {
-    #[cfg(not(target_os = "windows"))]
-    {
-        format!("\"{}\"", cleanup_path_for_python(path))
-    }
-    #[cfg(target_os = "windows")]
-    {
-        format!("r\"{}\"", cleanup_path_for_python(path))
-    }
+/// Whether `name` is a python identifier, optionally dotted like
+/// `module.function`. Only such names are registered, so a name never ends
+/// up as python source.
+fn is_python_name(name: &str) -> bool {
+    name.split('.').all(|part| {
+        let mut chars = part.chars();
+        chars.next().is_some_and(|c| c == '_' || c.is_alphabetic())
+            && chars.all(|c| c == '_' || c.is_alphanumeric())
+    })
 }
 
-async fn init_python(runner: &PyRunner, dir: PathBuf) {
-    // Make stdio safe before anything else (incl. main.py) runs - see PY_STDIO_GUARD.
+/// Installs the built-in `tauri` module and connects it to `app`, so python
+/// code can emit events.
+async fn init_tauri_module<R: Runtime>(
+    app: &AppHandle<R>,
+    runner: &PyRunner,
+) -> std::result::Result<(), async_py::PyRunnerError> {
+    let bridge_id = bridge::connect(app);
     runner
-        .run(PY_STDIO_GUARD)
-        .await
-        .expect("ERROR: Error initializing python stdio");
-    let sys_pyth_dir = print_path_for_python(&dir);
-    let path_import = format!(
-        r#"import sys
-sys.path = sys.path + [{}]
-"#,
-        sys_pyth_dir,
-    );
+        .call_function(
+            "_tauri_plugin_install_module",
+            vec!["tauri".into(), PY_TAURI_MODULE.into()],
+        )
+        .await?;
     runner
-        .run(&path_import)
-        .await
-        .expect("ERROR: Error setting python path");
+        .call_function("tauri._connect", vec![bridge_id.into()])
+        .await?;
+    Ok(())
+}
+
+/// Runs `main_py` like `PyRunner::run_file`, but the error of an exception
+/// it raises includes the traceback.
+async fn run_main_py(runner: &PyRunner, main_py: &PathBuf) -> crate::Result<()> {
+    py_context(
+        runner
+            .call_function(
+                "_tauri_plugin_run_file",
+                vec![cleanup_path_for_python(main_py).into()],
+            )
+            .await,
+        || format!("Error running '{}'", main_py.display()),
+    )?;
+    Ok(())
+}
+
+/// Passes the records of python's `logging` module to the `log` crate. Done
+/// after `main.py` ran, so it can still configure logging itself.
+async fn install_log_handler(runner: &PyRunner, startup: &Startup) -> crate::Result<()> {
+    if let Some(level) = startup.log_level.python_level() {
+        py_context(
+            runner
+                .call_function("tauri._install_log_handler", vec![level.into()])
+                .await,
+            || "Error installing the python log handler".into(),
+        )?;
+    }
+    Ok(())
+}
+
+/// Prepares a fresh runner for `main.py`. Used at startup and when python is
+/// restarted.
+async fn init_python<R: Runtime>(
+    app: &AppHandle<R>,
+    runner: &PyRunner,
+    startup: &Startup,
+) -> crate::Result<()> {
+    // Make stdio safe before anything else (incl. main.py) runs - see PY_STDIO_GUARD.
+    py_context(runner.run(PY_STDIO_GUARD).await, || {
+        "Error initializing python stdio".into()
+    })?;
+    py_context(runner.run(PY_PLUGIN_HELPERS).await, || {
+        "Error initializing python plugin helpers".into()
+    })?;
+    let sys_path_dirs: Vec<Value> = std::iter::once(&startup.dir)
+        .chain(&startup.sys_path)
+        .map(|dir| cleanup_path_for_python(dir).into())
+        .collect();
+    py_context(
+        runner
+            .call_function("_tauri_plugin_extend_path", vec![sys_path_dirs.into()])
+            .await,
+        || "Error setting python path".into(),
+    )?;
+    py_context(init_tauri_module(app, runner).await, || {
+        "Error initializing the python tauri module".into()
+    })?;
     #[cfg(feature = "venv")]
     {
-        let venv_dir = dir.join(".venv").join("lib");
+        let venv_dir = startup.venv.join("lib");
         if venv_dir.exists() {
-            runner
-                .set_venv(venv_dir.as_path())
-                .await
-                .expect("ERROR: Error setting venv for python");
+            py_context(runner.set_venv(venv_dir.as_path()).await, || {
+                "Error setting venv for python".into()
+            })?;
         }
     }
+    for code in &startup.before_main {
+        py_context(runner.run(code).await, || {
+            "Error running python code before main.py".into()
+        })?;
+    }
+    Ok(())
 }
 
-/// Initializes the plugin.
+/// Initializes the plugin and registers `python_functions` after `main.py` ran.
 pub fn init_and_register<R: Runtime>(python_functions: Vec<&'static str>) -> TauriPlugin<R> {
-    Builder::new("python")
-        .invoke_handler(tauri::generate_handler![
-            commands::run_python,
-            commands::register_function,
-            commands::call_function,
-            commands::read_variable
-        ])
-        .setup(|app, api| {
-            #[cfg(mobile)]
-            let python = mobile::init(app, api)?;
-            #[cfg(desktop)]
-            let python = desktop::init(app, api)?;
-            app.manage(python);
-            let runner = build_runner();
-            app.manage(runner);
-            app.manage(PluginState::default());
-
-            let mut dir = get_resource_dir(app);
-            let mut main_py = dir.join("main.py");
-            if !main_py.exists() {
-                println!(
-                    "Warning: 'src-tauri/main.py' seems not to be registered in 'tauri.conf.json'"
-                );
-                dir = get_src_python_dir();
-                main_py = dir.join("main.py");
-            }
-            tokio::runtime::Runtime::new()
-                .unwrap()
-                .block_on(async move {
-                    let runner = app.state::<PyRunner>().inner();
-                    init_python(runner, dir.to_path_buf()).await;
-                    runner
-                        .run_file(main_py.as_path())
-                        .await
-                        .expect("ERROR: Error running 'src-tauri/main.py'");
-                    register_python_functions(
-                        app,
-                        python_functions.iter().map(|s| s.to_string()).collect(),
-                    )
-                    .await;
-                    let functions = runner
-                        .read_variable("_tauri_plugin_functions")
-                        .await
-                        .unwrap_or_default();
-                    if let Ok(python_functions) = serde_json::from_value(functions) {
-                        register_python_functions(app, python_functions).await;
-                    }
-                });
-
-            Ok(())
-        })
+    python_functions
+        .into_iter()
+        .fold(Builder::new(), |builder, name| builder.function(name))
         .build()
 }
 
-async fn register_python_functions<R: Runtime>(app: &AppHandle<R>, python_functions: Vec<String>) {
-    for function_name in python_functions {
-        app.register_function(RegisterRequest {
-            python_function_call: function_name.clone(),
-            number_of_args: None,
-        })
-        .await
-        .unwrap();
+/// An entry of `_tauri_plugin_functions` in `main.py`: either just the name of
+/// the function, or e.g. `{"name": "fit_model", "timeoutMs": 1200000}`. The
+/// webviews allowed to call it can be limited with `windows` and `origins`.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum FunctionEntry {
+    Name(String),
+    #[serde(rename_all = "camelCase")]
+    Detailed {
+        name: String,
+        #[serde(default)]
+        timeout_ms: Option<u64>,
+        #[serde(default)]
+        windows: Option<Vec<String>>,
+        #[serde(default)]
+        origins: Option<Vec<String>>,
+    },
+}
+
+impl From<FunctionEntry> for RegisterRequest {
+    fn from(entry: FunctionEntry) -> Self {
+        match entry {
+            FunctionEntry::Name(name) => RegisterRequest {
+                python_function_call: name,
+                ..Default::default()
+            },
+            FunctionEntry::Detailed {
+                name,
+                timeout_ms,
+                windows,
+                origins,
+            } => RegisterRequest {
+                python_function_call: name,
+                timeout_ms,
+                windows,
+                origins,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// The functions main.py asks to register: the entries of
+/// `_tauri_plugin_functions` and the functions marked with `@tauri.command`.
+async fn python_functions(runner: &PyRunner) -> crate::Result<Vec<RegisterRequest>> {
+    let entries = py_context(
+        runner
+            .call_function("_tauri_plugin_function_entries", vec![])
+            .await,
+        || "Error collecting the python functions to register".into(),
+    )?;
+    let entries: Vec<FunctionEntry> = serde_json::from_value(entries)
+        .map_err(|err| Error::String(format!("Invalid entry in _tauri_plugin_functions: {err}")))?;
+    Ok(entries.into_iter().map(Into::into).collect())
+}
+
+async fn register_python_functions<R: Runtime>(
+    app: &AppHandle<R>,
+    python_functions: Vec<RegisterRequest>,
+) -> crate::Result<()> {
+    for request in python_functions {
+        app.register_function(request).await?;
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -11,20 +11,43 @@ pub struct StringRequest {
     pub value: String,
 }
 
-use serde_json::Value as JsMany;
+use serde_json::{Map, Value as JsMany};
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RegisterRequest {
     pub python_function_call: String,
     pub number_of_args: Option<u8>,
+    /// Default timeout for calls of this function that don't set their own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Labels of the windows or webviews that may call the function.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub windows: Option<Vec<String>>,
+    /// Origins of the webviews that may call the function, e.g. `tauri://localhost`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origins: Option<Vec<String>>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RunRequest {
     pub function_name: String,
+    #[serde(default)]
     pub args: Vec<JsMany>,
+    /// Passed to the python function as `**kwargs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kwargs: Option<Map<String, JsMany>>,
+    /// Chosen by the caller; lets the call be cancelled with `cancel_call`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub call_id: Option<String>,
+    /// Overrides the function's timeout for this call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Label of the webview that made the call, set by the plugin's commands.
+    /// Only that webview may cancel it.
+    #[serde(skip)]
+    pub webview: Option<String>,
 }
 
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
@@ -32,3 +55,162 @@ pub struct RunRequest {
 pub struct StringResponse {
     pub value: String,
 }
+
+/// Like [`StringResponse`], but keeps the Python return value as typed JSON
+/// instead of flattening it into a string.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValueResponse {
+    pub value: JsMany,
+}
+
+/// Message sent over the channel of `call_function_stream`: one `Item` per
+/// value yielded by the generator, then either `Done` or `Error`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", tag = "event", content = "data")]
+pub enum StreamMessage {
+    Item(JsMany),
+    /// Ends the items of a batch, numbered from 1, in streams started by the
+    /// frontend. The next batch is only sent once this one is acknowledged
+    /// with `ack_stream`.
+    Batch(u64),
+    Done,
+    Error(String),
+}
+
+/// Acknowledges the items of a stream up to `batch`, see [`StreamMessage::Batch`].
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamAck {
+    /// The id of the channel the stream is sent over.
+    pub channel_id: u32,
+    pub batch: u64,
+}
+
+/// Whether python is usable, as reported by `get_status`. Serialized as e.g.
+/// `{"status": "failed", "error": "..."}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", tag = "status")]
+pub enum StatusResponse {
+    /// The entry module hasn't finished yet.
+    #[default]
+    Starting,
+    Ready,
+    /// Initialization failed; `error` includes the python traceback.
+    Failed {
+        error: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// A line printed by python, as returned by `get_output` and emitted as
+/// `python://output`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputLine {
+    pub stream: OutputStream,
+    pub line: String,
+}
+
+/// Kind of a python function parameter, mirroring `inspect.Parameter.kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParameterKind {
+    PositionalOnly,
+    PositionalOrKeyword,
+    VarPositional,
+    KeywordOnly,
+    VarKeyword,
+}
+
+/// A registered python function, as returned by `list_functions`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionDescription {
+    pub name: String,
+    /// `None` if the signature couldn't be inspected, e.g. with RustPython.
+    pub parameters: Option<Vec<ParameterInfo>>,
+    /// The return type hint, if there is one.
+    pub returns: Option<TypeHint>,
+    pub doc: Option<String>,
+    pub is_async: bool,
+    pub is_generator: bool,
+    /// Default timeout of its calls.
+    pub timeout_ms: Option<u64>,
+}
+
+/// A parameter of a registered python function, recorded at registration.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParameterInfo {
+    pub name: String,
+    pub kind: ParameterKind,
+    pub has_default: bool,
+    /// `None` if the parameter has no type hint.
+    #[serde(default)]
+    pub annotation: Option<TypeHint>,
+    /// The default, if it has one that can be passed as JSON.
+    #[serde(default)]
+    pub default: Option<JsMany>,
+    /// The python `repr` of the default.
+    #[serde(default)]
+    pub default_repr: Option<String>,
+}
+
+/// A python type hint, as far as the plugin understands it. Anything else is
+/// [`TypeHint::Any`].
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum TypeHint {
+    #[serde(other)]
+    Any,
+    #[serde(rename = "none")]
+    NoneType,
+    Bool,
+    Int,
+    Float,
+    Str,
+    /// `list[T]`, `set[T]`, `tuple[T, ...]` and other sequences.
+    List {
+        items: Box<TypeHint>,
+    },
+    Tuple {
+        items: Vec<TypeHint>,
+    },
+    /// `dict[str, T]` and other mappings.
+    Dict {
+        values: Box<TypeHint>,
+    },
+    /// `Union[...]`, `Optional[T]` and `A | B`.
+    Union {
+        options: Vec<TypeHint>,
+    },
+    Literal {
+        values: Vec<JsMany>,
+    },
+    /// What a generator yields, e.g. `Iterator[T]` or `AsyncGenerator[T, None]`.
+    Iterator {
+        items: Box<TypeHint>,
+    },
+    /// A `TypedDict` or a dataclass.
+    Object {
+        name: String,
+        fields: Vec<FieldInfo>,
+    },
+}
+
+/// A field of a [`TypeHint::Object`].
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldInfo {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_hint: TypeHint,
+    pub required: bool,
+}
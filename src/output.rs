@@ -0,0 +1,60 @@
+//  Tauri Python Plugin
+//  © Copyright 2024, by Marco Mengelkoch
+//  Licensed under MIT License, see License file for more details
+//  git clone https://github.com/marcomq/tauri-plugin-python
+
+//! Output of python's `sys.stdout` and `sys.stderr`, forwarded line by line by
+//! the streams installed in `PY_STDIO_GUARD`.
+//!
+//! Every line is logged through the `log` crate with target `python::stdout`
+//! or `python::stderr`, so it ends up wherever the app's logger writes, also
+//! in release builds without a console. Optionally, the latest lines are kept
+//! for `get_output` and each line is emitted as `python://output`.
+
+use std::{collections::VecDeque, sync::Mutex};
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::models::{OutputLine, OutputStream};
+
+/// Event emitted for every line of output, if enabled.
+pub(crate) const OUTPUT_EVENT: &str = "python://output";
+
+#[derive(Default)]
+pub(crate) struct Output {
+    lines: Mutex<VecDeque<OutputLine>>,
+    /// How many lines are kept, `0` keeps none.
+    capacity: usize,
+    emit: bool,
+}
+
+impl Output {
+    pub(crate) fn new(capacity: usize, emit: bool) -> Self {
+        Self {
+            lines: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+            emit,
+        }
+    }
+
+    pub(crate) fn record<R: Runtime>(&self, app: &AppHandle<R>, line: OutputLine) {
+        match line.stream {
+            OutputStream::Stdout => log::info!(target: "python::stdout", "{}", line.line),
+            OutputStream::Stderr => log::warn!(target: "python::stderr", "{}", line.line),
+        }
+        if self.emit {
+            let _ = app.emit(OUTPUT_EVENT, &line);
+        }
+        if self.capacity > 0 {
+            let mut lines = self.lines.lock().unwrap();
+            if lines.len() == self.capacity {
+                lines.pop_front();
+            }
+            lines.push_back(line);
+        }
+    }
+
+    /// The kept lines, oldest first.
+    pub(crate) fn lines(&self) -> Vec<OutputLine> {
+        self.lines.lock().unwrap().iter().cloned().collect()
+    }
+}
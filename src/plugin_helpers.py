@@ -0,0 +1,400 @@
+#  Tauri Python Plugin
+#  © Copyright 2024, by Marco Mengelkoch
+#  Licensed under MIT License, see License file for more details
+#  git clone https://github.com/marcomq/tauri-plugin-python
+
+# Helpers used by the plugin itself. This runs once at startup in the same
+# global scope as main.py, so every name is prefixed with `_tauri_plugin_` to
+# stay out of the way of user code.
+
+
+def _tauri_plugin_resolve(name):
+    parts = name.split(".")
+    obj = globals()[parts[0]]
+    for part in parts[1:]:
+        obj = getattr(obj, part)
+    return obj
+
+
+def _tauri_plugin_extend_path(dirs):
+    import sys
+
+    sys.path = sys.path + list(dirs)
+
+
+def _tauri_plugin_install_module(name, source):
+    # Makes `source` importable as module `name`, and also available as a
+    # global of main.py's scope. The `@tauri.command` functions of imported
+    # modules are kept from the module it replaces after a restart, as these
+    # modules stay imported and don't run again; main.py adds its own again.
+    import sys
+    import types
+
+    module = types.ModuleType(name)
+    exec(compile(source, "<" + name + ">", "exec"), module.__dict__)
+    for entry in getattr(sys.modules.get(name), "_commands", []):
+        func = entry[1]
+        defined_in = sys.modules.get(getattr(func, "__module__", None))
+        if getattr(defined_in, "__dict__", None) is getattr(func, "__globals__", False):
+            module._commands.append(entry)
+    sys.modules[name] = module
+    globals()[name] = module
+
+
+def _tauri_plugin_function_entries():
+    # The functions to register after main.py ran: the entries of
+    # `_tauri_plugin_functions` and the functions marked with `@tauri.command`.
+    # The latter are made available under their name in this scope, as they
+    # may be defined in a module imported by main.py.
+    entries = list(globals().get("_tauri_plugin_functions", []))
+    for name, func, options in tauri._commands:
+        if globals().setdefault(name, func) is not func:
+            raise ValueError("@tauri.command '" + name + "' is already defined differently in main.py")
+        entries.append(dict(options, name=name))
+    return entries
+
+
+class _TauriPluginException(Exception):
+    # Carries the details of an exception raised by user code as JSON, since
+    # the Rust side only gets the string of an exception.
+    pass
+
+
+def _tauri_plugin_exception(error):
+    import json
+
+    details = {"typeName": type(error).__name__, "message": str(error)}
+    try:
+        import traceback
+
+        details["traceback"] = "".join(
+            traceback.format_exception(type(error), error, error.__traceback__)
+        )
+        details["frames"] = [
+            {"file": frame.filename, "line": frame.lineno, "function": frame.name}
+            for frame in traceback.extract_tb(error.__traceback__)
+        ]
+    except Exception:
+        pass
+    return _TauriPluginException(json.dumps(details))
+
+
+def _tauri_plugin_run_call(call_id, name, args, kwargs):
+    # A call that can be cancelled: while it runs, the plugin may raise
+    # KeyboardInterrupt in this thread. One that was cancelled while still
+    # queued doesn't run at all.
+    import threading
+
+    if not tauri._call_started(call_id, threading.get_ident()):
+        raise KeyboardInterrupt("call " + call_id + " was cancelled")
+    try:
+        return _tauri_plugin_resolve(name)(*args, **kwargs)
+    except Exception as error:
+        raise _tauri_plugin_exception(error) from None
+    finally:
+        tauri._call_finished(call_id)
+
+
+def _tauri_plugin_run_file(path):
+    # Runs main.py in this scope like `PyRunner::run_file` does, but an
+    # exception it raises keeps its details, e.g. the traceback.
+    import os
+    import sys
+
+    sys.path.insert(0, os.path.dirname(path))
+    try:
+        with open(path, encoding="utf-8") as file:
+            code = compile(file.read(), path, "exec")
+        exec(code, globals())
+    except Exception as error:
+        raise _tauri_plugin_exception(error) from None
+
+
+def _tauri_plugin_hints(func):
+    # The type hints of `func`, with string annotations resolved if possible.
+    try:
+        import typing
+
+        return typing.get_type_hints(func)
+    except Exception:
+        return dict(getattr(func, "__annotations__", None) or {})
+
+
+def _tauri_plugin_type(hint, depth=0):
+    # Describes a type hint as JSON, see `TypeHint` in models.rs. Anything not
+    # understood, or nested too deep (e.g. recursive types), is "any".
+    try:
+        return _tauri_plugin_describe_type(hint, depth)
+    except Exception:
+        return {"kind": "any"}
+
+
+def _tauri_plugin_describe_type(hint, depth):
+    import collections.abc as abc
+    import dataclasses
+    import typing
+
+    if depth > 8:
+        return {"kind": "any"}
+    if hint is None or hint is type(None):
+        return {"kind": "none"}
+    for simple, kind in ((bool, "bool"), (int, "int"), (float, "float"), (str, "str")):
+        if hint is simple:
+            return {"kind": kind}
+    origin = typing.get_origin(hint) or hint
+    args = typing.get_args(hint)
+    nested = lambda arg: _tauri_plugin_type(arg, depth + 1)
+    if origin is typing.Union or type(hint).__name__ == "UnionType":
+        return {"kind": "union", "options": [nested(arg) for arg in args]}
+    if origin is typing.Literal:
+        values = [arg for arg in args if arg is None or isinstance(arg, (bool, int, float, str))]
+        return {"kind": "literal", "values": values}
+    if origin in (abc.Iterator, abc.Generator, abc.AsyncIterator, abc.AsyncGenerator):
+        return {"kind": "iterator", "items": nested(args[0]) if args else {"kind": "any"}}
+    if origin is tuple and args and args[-1] is not Ellipsis:
+        return {"kind": "tuple", "items": [nested(arg) for arg in args]}
+    if origin in (list, tuple, set, frozenset, abc.Sequence, abc.Set, abc.Iterable):
+        return {"kind": "list", "items": nested(args[0]) if args else {"kind": "any"}}
+    if origin in (dict, abc.Mapping):
+        return {"kind": "dict", "values": nested(args[1]) if len(args) == 2 else {"kind": "any"}}
+    if isinstance(hint, type) and issubclass(hint, dict) and hasattr(hint, "__total__"):
+        # TypedDict
+        required = getattr(hint, "__required_keys__", None)
+        fields = [
+            {
+                "name": name,
+                "type": nested(field),
+                "required": name in required if required is not None else hint.__total__,
+            }
+            for name, field in _tauri_plugin_hints(hint).items()
+        ]
+        return {"kind": "object", "name": hint.__name__, "fields": fields}
+    if isinstance(hint, type) and dataclasses.is_dataclass(hint):
+        hints = _tauri_plugin_hints(hint)
+        fields = [
+            {
+                "name": field.name,
+                "type": nested(hints.get(field.name)),
+                "required": field.default is dataclasses.MISSING
+                and field.default_factory is dataclasses.MISSING,
+            }
+            for field in dataclasses.fields(hint)
+        ]
+        return {"kind": "object", "name": hint.__name__, "fields": fields}
+    return {"kind": "any"}
+
+
+def _tauri_plugin_parameters(func):
+    # Returns None if the signature can't be inspected, e.g. on RustPython
+    # (no `inspect`) or for some builtins; callers then skip validation.
+    try:
+        from inspect import signature
+
+        parameters = signature(func).parameters.values()
+    except Exception:
+        return None
+    hints = _tauri_plugin_hints(func)
+    return [
+        dict(
+            {
+                "name": p.name,
+                "kind": p.kind.name.lower(),
+                "hasDefault": p.default is not p.empty,
+                "annotation": _tauri_plugin_type(hints[p.name]) if p.name in hints else None,
+            },
+            **(_tauri_plugin_default(p.default) if p.default is not p.empty else {}),
+        )
+        for p in parameters
+    ]
+
+
+def _tauri_plugin_default(value):
+    # The default of a parameter, as JSON if possible and always as repr.
+    import json
+
+    try:
+        json.dumps(value)
+    except Exception:
+        return {"defaultRepr": repr(value)}
+    return {"default": value, "defaultRepr": repr(value)}
+
+
+def _tauri_plugin_code_flags(func):
+    # Checked directly (instead of via `inspect`) so this also works on RustPython.
+    code = getattr(func, "__code__", None)
+    return code.co_flags if code is not None else 0
+
+
+def _tauri_plugin_describe(name):
+    func = _tauri_plugin_resolve(name)
+    flags = _tauri_plugin_code_flags(func)
+    hints = _tauri_plugin_hints(func)
+    return {
+        "parameters": _tauri_plugin_parameters(func),
+        "returns": _tauri_plugin_type(hints["return"]) if "return" in hints else None,
+        "doc": getattr(func, "__doc__", None),
+        # CO_COROUTINE, CO_ASYNC_GENERATOR
+        "isAsync": bool(flags & (0x80 | 0x200)),
+        # CO_GENERATOR, CO_ASYNC_GENERATOR
+        "isGenerator": bool(flags & (0x20 | 0x200)),
+    }
+
+
+# `async def` functions all run on one persistent event loop in a background
+# thread, so the worker thread only schedules them and several can be awaited
+# at the same time. The loop is created on first use.
+_tauri_plugin_loop = None
+_tauri_plugin_tasks = {}
+_tauri_plugin_last_task = 0
+
+
+def _tauri_plugin_event_loop():
+    global _tauri_plugin_loop
+    if _tauri_plugin_loop is None:
+        import asyncio
+        import threading
+
+        loop = asyncio.new_event_loop()
+        threading.Thread(
+            target=loop.run_forever, name="tauri-plugin-python-asyncio", daemon=True
+        ).start()
+        _tauri_plugin_loop = loop
+    return _tauri_plugin_loop
+
+
+def _tauri_plugin_schedule(coroutine):
+    import asyncio
+
+    global _tauri_plugin_last_task
+    future = asyncio.run_coroutine_threadsafe(coroutine, _tauri_plugin_event_loop())
+    _tauri_plugin_last_task += 1
+    _tauri_plugin_tasks[_tauri_plugin_last_task] = future
+    return _tauri_plugin_last_task
+
+
+def _tauri_plugin_start_task(name, args, kwargs):
+    try:
+        return _tauri_plugin_schedule(_tauri_plugin_resolve(name)(*args, **kwargs))
+    except Exception as error:
+        raise _tauri_plugin_exception(error) from None
+
+
+def _tauri_plugin_poll_task(task_id):
+    future = _tauri_plugin_tasks[task_id]
+    if not future.done():
+        return {"done": False}
+    del _tauri_plugin_tasks[task_id]
+    try:
+        return {"done": True, "value": future.result()}
+    except Exception as error:
+        # The exception of a failed coroutine.
+        raise _tauri_plugin_exception(error) from None
+
+
+def _tauri_plugin_cancel_task(task_id):
+    future = _tauri_plugin_tasks.pop(task_id, None)
+    if future is not None:
+        future.cancel()
+
+
+# Generators are pulled in batches by the Rust side, so a generator only
+# advances as fast as its items are forwarded to the frontend.
+_tauri_plugin_streams = {}
+_tauri_plugin_last_stream = 0
+# The task collecting the current batch of an async generator, by stream id.
+_tauri_plugin_stream_tasks = {}
+
+
+def _tauri_plugin_start_stream(name, args, kwargs):
+    global _tauri_plugin_last_stream
+    _tauri_plugin_last_stream += 1
+    try:
+        stream = _tauri_plugin_resolve(name)(*args, **kwargs)
+    except Exception as error:
+        raise _tauri_plugin_exception(error) from None
+    _tauri_plugin_streams[_tauri_plugin_last_stream] = stream
+    return _tauri_plugin_last_stream
+
+
+def _tauri_plugin_raise_later(stream_id, error):
+    # Items collected before an exception are still delivered; the exception
+    # is raised by the next pull instead.
+    def failed():
+        raise error
+        yield
+
+    async def failed_async():
+        raise error
+        yield
+
+    is_async = hasattr(_tauri_plugin_streams[stream_id], "__anext__")
+    _tauri_plugin_streams[stream_id] = failed_async() if is_async else failed()
+
+
+async def _tauri_plugin_collect_async(stream_id, max_items):
+    items = []
+    try:
+        async for item in _tauri_plugin_streams[stream_id]:
+            items.append(item)
+            if len(items) >= max_items:
+                return {"items": items, "done": False}
+    except Exception as error:
+        if not items:
+            del _tauri_plugin_streams[stream_id]
+            raise
+        _tauri_plugin_raise_later(stream_id, error)
+        return {"items": items, "done": False}
+    finally:
+        _tauri_plugin_stream_tasks.pop(stream_id, None)
+    del _tauri_plugin_streams[stream_id]
+    return {"items": items, "done": True}
+
+
+def _tauri_plugin_next_items(stream_id, max_items):
+    stream = _tauri_plugin_streams[stream_id]
+    if hasattr(stream, "__anext__"):
+        # Async generators are advanced on the event loop; the caller awaits
+        # the returned task like an `async def` call.
+        task_id = _tauri_plugin_schedule(_tauri_plugin_collect_async(stream_id, max_items))
+        _tauri_plugin_stream_tasks[stream_id] = task_id
+        return {"task": task_id}
+    items = []
+    try:
+        for item in stream:
+            items.append(item)
+            if len(items) >= max_items:
+                return {"items": items, "done": False}
+    except Exception as error:
+        if not items:
+            del _tauri_plugin_streams[stream_id]
+            raise _tauri_plugin_exception(error) from None
+        _tauri_plugin_raise_later(stream_id, error)
+        return {"items": items, "done": False}
+    del _tauri_plugin_streams[stream_id]
+    return {"items": items, "done": True}
+
+
+def _tauri_plugin_close_stream(stream_id):
+    import asyncio
+
+    stream = _tauri_plugin_streams.pop(stream_id, None)
+    task_id = _tauri_plugin_stream_tasks.pop(stream_id, None)
+    if task_id is not None:
+        # The batch still being collected, e.g. after a timeout.
+        _tauri_plugin_cancel_task(task_id)
+    if hasattr(stream, "aclose"):
+        asyncio.run_coroutine_threadsafe(
+            _tauri_plugin_close_async(stream), _tauri_plugin_event_loop()
+        )
+    elif hasattr(stream, "close"):
+        stream.close()
+
+
+async def _tauri_plugin_close_async(stream):
+    # An async generator can't be closed while the cancelled task is still
+    # inside it, so wait until the cancellation got through.
+    import asyncio
+
+    while getattr(stream, "ag_running", False):
+        await asyncio.sleep(0.01)
+    await stream.aclose()
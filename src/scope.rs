@@ -0,0 +1,19 @@
+//  Tauri Python Plugin
+//  © Copyright 2024, by Marco Mengelkoch
+//  Licensed under MIT License, see License file for more details
+//  git clone https://github.com/marcomq/tauri-plugin-python
+
+//! The scope of the `allow-call` permission. Also included by `build.rs`, which
+//! generates its JSON schema, so capability files get validation and
+//! completion for the entries.
+
+use serde::{Deserialize, Serialize};
+
+/// Scope entry of the `allow-call` permission, listing the python functions
+/// a capability may call.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionScope {
+    /// The names the functions are registered as, e.g. `"export_report"`.
+    pub functions: Vec<String>,
+}
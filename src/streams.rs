@@ -0,0 +1,68 @@
+//  Tauri Python Plugin
+//  © Copyright 2024, by Marco Mengelkoch
+//  Licensed under MIT License, see License file for more details
+//  git clone https://github.com/marcomq/tauri-plugin-python
+
+//! Flow control of the streams started by the frontend. `Channel::send` only
+//! queues a message for the webview, so every batch of items is followed by a
+//! [`StreamMessage::Batch`](crate::models::StreamMessage::Batch), which the
+//! frontend acknowledges with `ack_stream` once it handled the items. The next
+//! batch is only pulled from python after that.
+
+use std::{collections::HashMap, sync::Mutex};
+use tokio::sync::watch;
+
+/// The last acknowledged batch of every stream, by channel id.
+#[derive(Default)]
+pub(crate) struct Streams(Mutex<HashMap<u32, watch::Sender<u64>>>);
+
+impl Streams {
+    /// Tracks the acknowledgements of the stream sent over `channel_id` until
+    /// the returned guard is dropped.
+    pub(crate) fn start(&self, channel_id: u32) -> StreamGuard<'_> {
+        let (acked, receiver) = watch::channel(0);
+        self.0.lock().unwrap().insert(channel_id, acked);
+        StreamGuard {
+            streams: self,
+            channel_id,
+            acked: receiver,
+        }
+    }
+
+    /// Records that the frontend handled the items up to `batch`. Returns
+    /// `false` if there is no such stream (anymore).
+    pub(crate) fn ack(&self, channel_id: u32, batch: u64) -> bool {
+        let streams = self.0.lock().unwrap();
+        let Some(acked) = streams.get(&channel_id) else {
+            return false;
+        };
+        acked.send_if_modified(|acked| {
+            let newer = batch > *acked;
+            if newer {
+                *acked = batch;
+            }
+            newer
+        });
+        true
+    }
+}
+
+pub(crate) struct StreamGuard<'a> {
+    streams: &'a Streams,
+    channel_id: u32,
+    acked: watch::Receiver<u64>,
+}
+
+impl StreamGuard<'_> {
+    /// Waits until `batch` is acknowledged.
+    pub(crate) async fn acked(&mut self, batch: u64) {
+        // The sender is only removed along with the guard.
+        let _ = self.acked.wait_for(|acked| *acked >= batch).await;
+    }
+}
+
+impl Drop for StreamGuard<'_> {
+    fn drop(&mut self) {
+        self.streams.0.lock().unwrap().remove(&self.channel_id);
+    }
+}
@@ -0,0 +1,158 @@
+#  Tauri Python Plugin
+#  © Copyright 2024, by Marco Mengelkoch
+#  Licensed under MIT License, see License file for more details
+#  git clone https://github.com/marcomq/tauri-plugin-python
+
+"""Access to the tauri app from python.
+
+Installed by the plugin before main.py runs, so main.py and everything it
+imports can use `import tauri`.
+"""
+
+import json
+
+_bridge_id = None
+
+# Messages waiting to be picked up by the plugin. Only used without the native
+# bridge (RustPython), in which case they are delivered once the current call
+# from the plugin returns.
+_outbox = []
+
+# Functions marked with `@command`, as (name, function, options). The plugin
+# registers them after main.py ran.
+_commands = []
+
+
+def _connect(bridge_id):
+    global _bridge_id
+    _bridge_id = bridge_id
+
+
+class RustError(RuntimeError):
+    """Raised by `call_rust` if the Rust handler returned an error."""
+
+
+def _native_dispatch():
+    try:
+        from _tauri_plugin_bridge import dispatch
+    except ImportError:
+        return None
+    return dispatch
+
+
+def _send(kind, data):
+    message = json.dumps(data)
+    dispatch = _native_dispatch()
+    if dispatch is None:
+        _outbox.append([kind, message])
+        return None
+    return json.loads(dispatch(_bridge_id, kind, message))
+
+
+def _drain():
+    messages = _outbox[:]
+    del _outbox[: len(messages)]
+    return messages
+
+
+def _call_started(call_id, thread_id):
+    # Returns False if the call was cancelled before it got to run. Without the
+    # native bridge, running calls can't be interrupted, so this isn't tracked.
+    if _native_dispatch() is None:
+        return True
+    return _send("call_started", {"callId": call_id, "threadId": thread_id})
+
+
+def _call_finished(call_id):
+    if _native_dispatch() is not None:
+        _send("call_finished", {"callId": call_id})
+
+
+def _output(stream, line):
+    # Called by the sys.stdout / sys.stderr replacements for every line.
+    _send("output", {"stream": stream, "line": line})
+
+
+def _install_log_handler(level):
+    # Passes the records of the `logging` module, from any thread, to the Rust
+    # `log` crate. Called after main.py ran, so `logging.basicConfig()` there
+    # still works, and the root level is only set if main.py didn't configure
+    # logging. Replaces the handler of an earlier runner, as the logging module
+    # is shared by all of them with PyO3.
+    try:
+        import logging
+    except ImportError:
+        return
+
+    class _TauriLogHandler(logging.Handler):
+        def emit(self, record):
+            try:
+                _send(
+                    "log",
+                    {
+                        "level": record.levelno,
+                        "name": record.name,
+                        "message": self.format(record),
+                        "file": record.pathname,
+                        "line": record.lineno,
+                    },
+                )
+            except Exception:
+                self.handleError(record)
+
+    root = logging.getLogger()
+    for handler in root.handlers[:]:
+        if type(handler).__name__ == "_TauriLogHandler":
+            root.removeHandler(handler)
+    if not root.handlers:
+        root.setLevel(level)
+    root.addHandler(_TauriLogHandler(level))
+
+
+def command(func=None, *, name=None, timeout=None, windows=None, origins=None):
+    """Makes the decorated function callable from the frontend.
+
+    Use it as `@tauri.command`, or with arguments like
+    `@tauri.command(name="fit", timeout=600, windows=["main"])`: `name` is the
+    name to call it by (its own name by default), `timeout` the default timeout
+    of its calls in seconds, and `windows` / `origins` limit the webviews that
+    may call it.
+    """
+
+    def register(func):
+        options = {}
+        if timeout is not None:
+            options["timeoutMs"] = int(timeout * 1000)
+        if windows is not None:
+            options["windows"] = list(windows)
+        if origins is not None:
+            options["origins"] = list(origins)
+        _commands.append((name or func.__name__, func, options))
+        return func
+
+    if func is None:
+        return register
+    return register(func)
+
+
+def emit(event, payload=None):
+    """Emits `event` to all windows. `payload` must be JSON serializable."""
+    _send("emit", {"event": event, "payload": payload})
+
+
+def emit_to(window_label, event, payload=None):
+    """Emits `event` only to the window or webview labeled `window_label`."""
+    _send("emit_to", {"target": window_label, "event": event, "payload": payload})
+
+
+def call_rust(name, *args):
+    """Calls the Rust handler registered as `name` and returns its result.
+
+    Arguments and result are passed as JSON. Requires the PyO3 backend.
+    """
+    if _native_dispatch() is None:
+        raise NotImplementedError("tauri.call_rust requires the PyO3 backend")
+    result = _send("call_rust", {"name": name, "args": list(args)})
+    if "error" in result:
+        raise RustError(result["error"])
+    return result["value"]
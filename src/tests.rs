@@ -6,70 +6,1791 @@
 use super::*;
 use tauri::{
     test::{self, MockRuntime},
-    AppHandle,
+    AppHandle, Listener,
 };
 
 /// Creates a mock Tauri app and initializes the PyRunner state.
 /// It also runs some initial Python code to set up a variable and a function for testing.
 async fn mock_app_handle() -> AppHandle<MockRuntime> {
+    mock_app_with_state(PluginState::default()).await
+}
+
+/// Like [`mock_app_handle`], but with the given plugin state, e.g. to add Rust handlers.
+async fn mock_app_with_state(state: PluginState) -> AppHandle<MockRuntime> {
+    let startup = Startup {
+        dir: std::env::temp_dir(),
+        ..Default::default()
+    };
+    mock_app_with_startup(state, startup).await
+}
+
+/// Like [`mock_app_with_state`], but python is started as `startup` tells,
+/// e.g. with a default timeout.
+async fn mock_app_with_startup(state: PluginState, startup: Startup) -> AppHandle<MockRuntime> {
     let app = test::mock_app();
+    state.status.send_if_modified(|status| {
+        let starting = *status == StatusResponse::Starting;
+        if starting {
+            *status = StatusResponse::Ready;
+        }
+        starting
+    });
+    app.manage(Interpreter::new(PyRunner::new(), startup));
+    app.manage(state);
+
+    let runner = app.runner();
+    runner.run(PY_PLUGIN_HELPERS).await.unwrap();
+    init_tauri_module(app.handle(), &runner).await.unwrap();
+    runner
+        .run("my_var = 123\ndef my_func(a, b):\n  return a + b")
+        .await
+        .unwrap();
+
+    app.handle().clone()
+}
+
+#[tokio::test]
+async fn test_read_variable() {
+    let app = mock_app_handle().await;
+    let payload = StringRequest {
+        value: "my_var".into(),
+    };
+    let response = app.read_variable(payload).await.unwrap();
+    assert_eq!(response.value, "123");
+}
+
+#[tokio::test]
+async fn test_frontend_reads_only_exposed_variables() {
+    let app = mock_app_with_state(PluginState {
+        variables: Mutex::new(["my_var".to_string()].into()),
+        ..Default::default()
+    })
+    .await;
+    let read = |value: &str| {
+        commands::read_variable(
+            app.clone(),
+            StringRequest {
+                value: value.into(),
+            },
+        )
+    };
+    assert_eq!(read("my_var").await.unwrap().value, "123");
+    // Not exposed; also rejected for names python would fail to evaluate.
+    for name in ["my_func", "__import__('os').getcwd()"] {
+        let err = read(name).await.unwrap_err();
+        assert!(matches!(&err, Error::NotExposed(n) if n == name), "{err:?}");
+        assert_eq!(err.kind(), "notExposed");
+    }
+}
+
+#[tokio::test]
+async fn test_run_python() {
+    let app = mock_app_handle().await;
+    let payload = StringRequest {
+        value: "new_var = 456".into(),
+    };
+    app.run_python(payload).await.unwrap();
+
+    // Verify the code was run by reading the variable back
+    let read_payload = StringRequest {
+        value: "new_var".into(),
+    };
+    let response = app.read_variable(read_payload).await.unwrap();
+    assert_eq!(response.value, "456");
+}
+
+#[tokio::test]
+async fn test_register_and_call_function() {
+    let app = mock_app_handle().await;
+
+    // 1. Register the function
+    let register_payload = RegisterRequest {
+        python_function_call: "my_func".into(),
+        number_of_args: Some(2),
+        ..Default::default()
+    };
+    app.register_function(register_payload).await.unwrap();
+
+    // 2. Call the registered function
+    let call_payload = RunRequest {
+        function_name: "my_func".into(),
+        args: vec![serde_json::json!(10), serde_json::json!(20)],
+        ..Default::default()
+    };
+    let response = app.call_function(call_payload).await.unwrap();
+    assert_eq!(response.value, "30");
+}
+
+// call_function_json must keep the JSON type of the result, so a number and
+// the string with the same digits stay distinguishable.
+#[tokio::test]
+async fn test_call_function_json_keeps_types() {
+    let app = mock_app_handle().await;
+    let runner = app.runner();
+    runner
+        .run("def as_text(a, b):\n  return str(a + b)\ndef as_dict(a):\n  return {'a': a, 'l': [a, None]}")
+        .await
+        .unwrap();
+    for name in ["my_func", "as_text", "as_dict"] {
+        app.register_function(RegisterRequest {
+            python_function_call: name.into(),
+            number_of_args: None,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    }
+    let number = app
+        .call_function_json(RunRequest {
+            function_name: "my_func".into(),
+            args: vec![serde_json::json!(10), serde_json::json!(20)],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(number.value, serde_json::json!(30));
+    let text = app
+        .call_function_json(RunRequest {
+            function_name: "as_text".into(),
+            args: vec![serde_json::json!(10), serde_json::json!(20)],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(text.value, serde_json::json!("30"));
+    let dict = app
+        .call_function_json(RunRequest {
+            function_name: "as_dict".into(),
+            args: vec![serde_json::json!(1)],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(dict.value, serde_json::json!({"a": 1, "l": [1, null]}));
+
+    // The string variant is unchanged for compatibility.
+    let legacy = app
+        .call_function(RunRequest {
+            function_name: "as_text".into(),
+            args: vec![serde_json::json!(10), serde_json::json!(20)],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(legacy.value, "30");
+}
+
+// Arguments are checked against the type hints recorded at registration.
+#[cfg(all(feature = "pyo3", not(feature = "rustpython")))]
+#[tokio::test]
+async fn test_arguments_are_checked_against_type_hints() {
+    let app = mock_app_handle().await;
+    app.run_python(StringRequest {
+        value: r#"
+from typing import Literal, Optional
+
+def area(w: int, h: float = 1.0, *, unit: Literal["m", "ft"] = "m", tags: Optional[list[str]] = None):
+    return w * h
+"#
+        .into(),
+    })
+    .await
+    .unwrap();
+    app.register_function(RegisterRequest {
+        python_function_call: "area".into(),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let call = |args: serde_json::Value, kwargs: serde_json::Value| {
+        app.call_function_json(RunRequest {
+            function_name: "area".into(),
+            args: serde_json::from_value(args).unwrap(),
+            kwargs: serde_json::from_value(kwargs).unwrap(),
+            ..Default::default()
+        })
+    };
+    let value = call(
+        serde_json::json!([2, 1.5]),
+        serde_json::json!({"unit": "ft", "tags": ["x"]}),
+    );
+    assert_eq!(value.await.unwrap().value, serde_json::json!(3.0));
+    let value = call(serde_json::json!([2]), serde_json::json!({"tags": null}));
+    assert_eq!(value.await.unwrap().value, serde_json::json!(2.0));
+
+    for (args, kwargs, parameter) in [
+        (serde_json::json!(["2"]), serde_json::json!(null), Some("w")),
+        (serde_json::json!([]), serde_json::json!(null), Some("w")),
+        (serde_json::json!([1, 2, 3]), serde_json::json!(null), None),
+        // `None` isn't a float, although `h` has a default.
+        (
+            serde_json::json!([2, null]),
+            serde_json::json!(null),
+            Some("h"),
+        ),
+        (
+            serde_json::json!([1]),
+            serde_json::json!({"unit": "km"}),
+            Some("unit"),
+        ),
+        (
+            serde_json::json!([1]),
+            serde_json::json!({"tags": [1]}),
+            Some("tags"),
+        ),
+    ] {
+        let err = call(args.clone(), kwargs).await.unwrap_err();
+        assert_eq!(err.kind(), "invalidArgument", "{args}: {err}");
+        let err = serde_json::to_value(&err).unwrap();
+        assert_eq!(
+            err["parameter"],
+            serde_json::json!(parameter),
+            "{args}: {err}"
+        );
+    }
+    let err = call(serde_json::json!(["2"]), serde_json::json!(null));
+    assert_eq!(
+        err.await.unwrap_err().to_string(),
+        "Argument 'w' of function 'area' must be int, got a string"
+    );
+}
+
+// Keyword arguments are passed through as `**kwargs`, including keyword-only
+// parameters that can't be reached positionally.
+#[tokio::test]
+async fn test_call_function_with_kwargs() {
+    let app = mock_app_handle().await;
+    let runner = app.runner();
+    runner
+        .run("def scale(x, factor=2, *, offset=0):\n  return x * factor + offset")
+        .await
+        .unwrap();
+    app.register_function(RegisterRequest {
+        python_function_call: "scale".into(),
+        number_of_args: None,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let mut kwargs = serde_json::Map::new();
+    kwargs.insert("offset".into(), serde_json::json!(1));
+    kwargs.insert("factor".into(), serde_json::json!(10));
+    let response = app
+        .call_function_json(RunRequest {
+            function_name: "scale".into(),
+            args: vec![serde_json::json!(4)],
+            kwargs: Some(kwargs),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.value, serde_json::json!(41));
+}
+
+// Unknown keyword names are rejected from the signature recorded at
+// registration, before entering Python. Needs `inspect`, so pyo3 only.
+#[cfg(all(feature = "pyo3", not(feature = "rustpython")))]
+#[tokio::test]
+async fn test_call_function_rejects_unknown_kwargs() {
+    let app = mock_app_handle().await;
+    let runner = app.runner();
+    runner
+        .run("calls = 0\ndef counted(x, *, flag=False):\n  global calls\n  calls += 1\n  return x")
+        .await
+        .unwrap();
+    app.register_function(RegisterRequest {
+        python_function_call: "counted".into(),
+        number_of_args: None,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let mut kwargs = serde_json::Map::new();
+    kwargs.insert("flga".into(), serde_json::json!(true));
+    let err = app
+        .call_function(RunRequest {
+            function_name: "counted".into(),
+            args: vec![serde_json::json!(1)],
+            kwargs: Some(kwargs),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("has no keyword argument 'flga'"),
+        "unexpected error: {err}"
+    );
+
+    let mut kwargs = serde_json::Map::new();
+    kwargs.insert("x".into(), serde_json::json!(2));
+    let err = app
+        .call_function(RunRequest {
+            function_name: "counted".into(),
+            args: vec![serde_json::json!(1)],
+            kwargs: Some(kwargs),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("got multiple values for argument 'x'"),
+        "unexpected error: {err}"
+    );
+
+    let calls = app
+        .read_variable(StringRequest {
+            value: "calls".into(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(calls.value, "0", "python must not have been entered");
+}
+
+// `async def` functions are awaited on the plugin's event loop instead of
+// returning a stringified coroutine object.
+#[tokio::test]
+async fn test_call_async_function() {
+    let app = mock_app_handle().await;
+    let runner = app.runner();
+    runner
+        .run("import asyncio\nasync def add_later(a, b):\n  await asyncio.sleep(0.01)\n  return a + b")
+        .await
+        .unwrap();
+    app.register_function(RegisterRequest {
+        python_function_call: "add_later".into(),
+        number_of_args: None,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let response = app
+        .call_function_json(RunRequest {
+            function_name: "add_later".into(),
+            args: vec![serde_json::json!(1), serde_json::json!(2)],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.value, serde_json::json!(3));
+}
+
+// Several coroutines can be in flight at once, and a slow one doesn't block
+// the single worker for plain calls in the meantime.
+#[tokio::test]
+async fn test_async_calls_run_concurrently() {
+    let app = mock_app_handle().await;
+    let runner = app.runner();
+    runner
+        .run("import asyncio\nasync def slow(x):\n  await asyncio.sleep(1)\n  return x")
+        .await
+        .unwrap();
+    app.register_function(RegisterRequest {
+        python_function_call: "slow".into(),
+        number_of_args: None,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    app.register_function(RegisterRequest {
+        python_function_call: "my_func".into(),
+        number_of_args: None,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let call_slow = |x: i64| {
+        app.call_function_json(RunRequest {
+            function_name: "slow".into(),
+            args: vec![serde_json::json!(x)],
+            ..Default::default()
+        })
+    };
+    let started = std::time::Instant::now();
+    let (first, second, plain) = tokio::join!(
+        call_slow(1),
+        call_slow(2),
+        app.call_function_json(RunRequest {
+            function_name: "my_func".into(),
+            args: vec![serde_json::json!(1), serde_json::json!(2)],
+            ..Default::default()
+        })
+    );
+    assert_eq!(first.unwrap().value, serde_json::json!(1));
+    assert_eq!(second.unwrap().value, serde_json::json!(2));
+    assert_eq!(plain.unwrap().value, serde_json::json!(3));
+    assert!(
+        started.elapsed() < Duration::from_millis(1800),
+        "two 1s coroutines should overlap, took {:?}",
+        started.elapsed()
+    );
+}
+
+// Exceptions raised inside a coroutine reach the caller like sync ones.
+#[tokio::test]
+async fn test_async_function_error_propagates() {
+    let app = mock_app_handle().await;
+    let runner = app.runner();
+    runner
+        .run("async def async_raiser():\n  raise ValueError('async boom')")
+        .await
+        .unwrap();
+    app.register_function(RegisterRequest {
+        python_function_call: "async_raiser".into(),
+        number_of_args: None,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let err = app
+        .call_function(RunRequest {
+            function_name: "async_raiser".into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("async boom"), "unexpected error: {err}");
+}
+
+// Cancelling a running call interrupts the python code with KeyboardInterrupt
+// and frees the worker for later calls. Needs the native bridge, so pyo3 only.
+#[cfg(all(feature = "pyo3", not(feature = "rustpython")))]
+#[tokio::test]
+async fn test_cancel_running_call() {
+    let app = mock_app_handle().await;
+    let runner = app.runner();
+    runner
+        .run("interrupted = False\ndef busy():\n  global interrupted\n  try:\n    while True:\n      pass\n  except KeyboardInterrupt:\n    interrupted = True\n    raise")
+        .await
+        .unwrap();
+    app.register_function(RegisterRequest {
+        python_function_call: "busy".into(),
+        number_of_args: None,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let (result, cancelled) = tokio::join!(
+        app.call_function(RunRequest {
+            function_name: "busy".into(),
+            call_id: Some("busy-1".into()),
+            ..Default::default()
+        }),
+        async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            app.cancel_call(StringRequest {
+                value: "busy-1".into(),
+            })
+            .await
+        }
+    );
+    cancelled.unwrap();
+    assert!(
+        matches!(result, Err(Error::Cancelled(ref id)) if id == "busy-1"),
+        "unexpected result: {result:?}"
+    );
+    let interrupted = app
+        .read_variable(StringRequest {
+            value: "interrupted".into(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(interrupted.value, "true");
+}
+
+// Cancelling an `async def` call rejects it right away and cancels the task.
+#[tokio::test]
+async fn test_cancel_async_call() {
+    let app = mock_app_handle().await;
+    let runner = app.runner();
+    runner
+        .run("import asyncio\nasync def wait_long():\n  await asyncio.sleep(60)")
+        .await
+        .unwrap();
+    app.register_function(RegisterRequest {
+        python_function_call: "wait_long".into(),
+        number_of_args: None,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let started = std::time::Instant::now();
+    let (result, cancelled) = tokio::join!(
+        app.call_function(RunRequest {
+            function_name: "wait_long".into(),
+            call_id: Some("wait-1".into()),
+            ..Default::default()
+        }),
+        async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            app.cancel_call(StringRequest {
+                value: "wait-1".into(),
+            })
+            .await
+        }
+    );
+    cancelled.unwrap();
+    assert!(matches!(result, Err(Error::Cancelled(_))), "{result:?}");
+    assert!(started.elapsed() < Duration::from_secs(5));
+    // The call id is free again once the call is gone.
+    let err = app
+        .cancel_call(StringRequest {
+            value: "wait-1".into(),
+        })
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("No running call"), "unexpected error: {err}");
+}
+
+// A webview can only cancel the calls it made, and the ids of calls made
+// without one can't be used at all.
+#[tokio::test]
+async fn test_cancel_only_own_calls() {
+    let app = mock_app_handle().await;
+    let state = app.state::<PluginState>();
+    let calls = &state.calls;
+    let _main = calls.start("report", Some("main")).unwrap();
+    assert!(!calls.cancel("report", Some("sandbox")));
+    assert!(cancel_running_call(&state, "report", Some("sandbox")).is_err());
+    assert!(cancel_running_call(&state, "report", Some("main")).is_ok());
+
+    let _rust = calls.start("from-rust", None).unwrap();
+    assert!(!calls.cancel("from-rust", Some("main")));
+    assert!(calls.cancel("from-rust", None));
+
+    let unnamed = calls.start_unnamed();
+    let err = cancel_running_call(&state, unnamed.call_id(), None)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("reserved for the plugin"), "{err}");
+    assert!(calls.start("_tauri_plugin_1", Some("main")).is_err());
+}
+
+// Interrupting a running call waits for the GIL, which python may hold for
+// long, but neither `cancel` nor other calls wait with it.
+#[cfg(all(feature = "pyo3", not(feature = "rustpython")))]
+#[tokio::test]
+async fn test_cancel_does_not_wait_for_the_gil() {
+    let app = mock_app_handle().await;
+    let calls = &app.state::<PluginState>().inner().calls;
+    let _call = calls.start("held", None).unwrap();
+    // No python thread has this id, so the interrupt doesn't hit anything.
+    assert!(calls.python_started("held", u64::MAX));
+    let (held, gil_held) = std::sync::mpsc::channel();
+    let holder = std::thread::spawn(move || {
+        pyo3::Python::attach(|_py| {
+            held.send(()).unwrap();
+            std::thread::sleep(Duration::from_millis(500));
+        })
+    });
+    gil_held.recv().unwrap();
+    let started = std::time::Instant::now();
+    assert!(calls.cancel("held", None));
+    drop(calls.start("other", None).unwrap());
+    assert!(
+        started.elapsed() < Duration::from_millis(250),
+        "took {:?}",
+        started.elapsed()
+    );
+    holder.join().unwrap();
+}
+
+// A per-call timeout rejects with a timeout error naming the function; the
+// worker finishes the call in the background and stays usable.
+#[tokio::test]
+async fn test_call_timeout() {
+    let app = mock_app_handle().await;
+    let runner = app.runner();
+    runner
+        .run("import time\ndef sleepy(s):\n  time.sleep(s)\n  return s")
+        .await
+        .unwrap();
+    app.register_function(RegisterRequest {
+        python_function_call: "sleepy".into(),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let result = app
+        .call_function(RunRequest {
+            function_name: "sleepy".into(),
+            args: vec![serde_json::json!(0.5)],
+            timeout_ms: Some(100),
+            ..Default::default()
+        })
+        .await;
+    assert!(
+        matches!(&result, Err(Error::Timeout { function, timeout_ms: 100 }) if function == "sleepy"),
+        "unexpected result: {result:?}"
+    );
+    let response = app
+        .call_function(RunRequest {
+            function_name: "sleepy".into(),
+            args: vec![serde_json::json!(0)],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.value, "0");
+}
+
+// Without a timeout of the call or the function, the default timeout of the
+// interpreter applies and is reported like any other timeout - also for an
+// `async def` function that never returns.
+#[tokio::test]
+async fn test_default_timeout() {
+    let startup = Startup {
+        dir: std::env::temp_dir(),
+        timeout: Some(Duration::from_millis(200)),
+        ..Default::default()
+    };
+    let app = mock_app_with_startup(PluginState::default(), startup).await;
+    app.runner()
+        .run("import asyncio, time\nasync def hangs():\n  await asyncio.sleep(3600)\ndef sleepy():\n  time.sleep(1)")
+        .await
+        .unwrap();
+    for name in ["hangs", "sleepy"] {
+        app.register_function(RegisterRequest {
+            python_function_call: name.into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let result = app
+            .call_function(RunRequest {
+                function_name: name.into(),
+                ..Default::default()
+            })
+            .await;
+        assert!(
+            matches!(&result, Err(Error::Timeout { function, timeout_ms: 200 }) if function == name),
+            "unexpected result: {result:?}"
+        );
+        assert_eq!(result.unwrap_err().kind(), "timeout");
+    }
+}
+
+// The timeout given at registration applies to every call, unless the call
+// sets its own, which may also be longer.
+#[tokio::test]
+async fn test_function_timeout_from_registration() {
+    let app = mock_app_handle().await;
+    let runner = app.runner();
+    runner
+        .run("import asyncio\nasync def nap(s):\n  await asyncio.sleep(s)\n  return s")
+        .await
+        .unwrap();
+    app.register_function(RegisterRequest {
+        python_function_call: "nap".into(),
+        timeout_ms: Some(50),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let nap = |timeout_ms| {
+        app.call_function_json(RunRequest {
+            function_name: "nap".into(),
+            args: vec![serde_json::json!(0.3)],
+            timeout_ms,
+            ..Default::default()
+        })
+    };
+    let result = nap(None).await;
+    assert!(
+        matches!(result, Err(Error::Timeout { timeout_ms: 50, .. })),
+        "unexpected result: {result:?}"
+    );
+    assert_eq!(nap(Some(5000)).await.unwrap().value, serde_json::json!(0.3));
+}
+
+#[test]
+fn test_function_entries_with_timeout() {
+    let entries: Vec<FunctionEntry> = serde_json::from_value(serde_json::json!([
+        "plain",
+        {"name": "fit", "timeoutMs": 1200, "windows": ["settings"]}
+    ]))
+    .unwrap();
+    let requests: Vec<RegisterRequest> = entries.into_iter().map(Into::into).collect();
+    assert_eq!(requests[0].python_function_call, "plain");
+    assert_eq!(requests[0].timeout_ms, None);
+    assert_eq!(requests[1].python_function_call, "fit");
+    assert_eq!(requests[1].timeout_ms, Some(1200));
+    assert_eq!(requests[1].windows, Some(vec!["settings".to_string()]));
+}
+
+#[test]
+fn test_access_of_restricted_function() {
+    let access = Access {
+        windows: Some(vec!["settings".into()]),
+        origins: None,
+    };
+    assert!(access.allows(&["settings"], Some("tauri://localhost")));
+    assert!(!access.allows(&["preview"], Some("tauri://localhost")));
+    let access = Access {
+        windows: None,
+        origins: Some(vec!["tauri://localhost".into()]),
+    };
+    assert!(access.allows(&["preview"], Some("tauri://localhost")));
+    assert!(!access.allows(&["preview"], Some("https://evil.example")));
+    assert!(!access.allows(&["preview"], None));
+    assert!(Access::default().allows(&["preview"], None));
+}
+
+#[tokio::test]
+async fn test_call_from_other_window_is_rejected() {
+    let app = mock_app_handle().await;
+    app.register_function(RegisterRequest {
+        python_function_call: "my_func".into(),
+        windows: Some(vec!["settings".into()]),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let request = || RunRequest {
+        function_name: "my_func".into(),
+        args: vec![serde_json::json!(1), serde_json::json!(2)],
+        ..Default::default()
+    };
+
+    let preview = tauri::WebviewWindowBuilder::new(&app, "preview", Default::default())
+        .build()
+        .unwrap();
+    let result = check_access(&app, preview.as_ref(), "my_func");
+    assert!(
+        matches!(&result, Err(Error::Forbidden { webview, .. }) if webview == "preview"),
+        "{result:?}"
+    );
+
+    let settings = tauri::WebviewWindowBuilder::new(&app, "settings", Default::default())
+        .build()
+        .unwrap();
+    check_access(&app, settings.as_ref(), "my_func").unwrap();
+    let result = app.call_function(request()).await;
+    assert_eq!(result.unwrap().value, "3");
+}
+
+#[tokio::test]
+async fn test_list_functions() {
+    let app = mock_app_handle().await;
+    app.run_python(StringRequest {
+        value: "def documented(x, y=2):\n  'Adds y to x.'\n  return x + y".into(),
+    })
+    .await
+    .unwrap();
+    app.register_function(RegisterRequest {
+        python_function_call: "documented".into(),
+        timeout_ms: Some(1500),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    app.register_function(RegisterRequest {
+        python_function_call: "my_func".into(),
+        windows: Some(vec!["settings".into()]),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let functions = app.list_functions().await.unwrap();
+    let names: Vec<&str> = functions.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["documented", "my_func"]);
+    let documented = &functions[0];
+    assert_eq!(documented.doc.as_deref(), Some("Adds y to x."));
+    assert_eq!(documented.timeout_ms, Some(1500));
+    assert!(!documented.is_async && !documented.is_generator);
+    #[cfg(all(feature = "pyo3", not(feature = "rustpython")))]
+    {
+        let parameters = documented.parameters.as_ref().unwrap();
+        assert_eq!(parameters[1].name, "y");
+        assert_eq!(parameters[1].default, Some(serde_json::json!(2)));
+        assert_eq!(parameters[1].default_repr.as_deref(), Some("2"));
+    }
+
+    // The frontend only sees what it may call.
+    let preview = tauri::WebviewWindowBuilder::new(&app, "preview", Default::default())
+        .build()
+        .unwrap();
+    let listed = commands::list_functions(app.clone(), preview.as_ref().clone()).await;
+    let names: Vec<String> = listed.unwrap().into_iter().map(|f| f.name).collect();
+    assert_eq!(names, ["documented"]);
+}
+
+#[test]
+fn test_scope_of_allow_call_permission() {
+    let report = FunctionScope {
+        functions: vec!["export_report".into()],
+    };
+    let delete = FunctionScope {
+        functions: vec!["delete_all".into()],
+    };
+    // Just `allow-call-function`: no scope, everything registered.
+    assert!(check_scope("delete_all", "main", &[], &[]).is_ok());
+    assert!(check_scope("export_report", "main", &[&report], &[]).is_ok());
+    let err = check_scope("delete_all", "main", &[&report], &[]).unwrap_err();
+    assert_eq!(err.kind(), "forbidden");
+    assert!(check_scope("delete_all", "main", &[], &[&delete]).is_err());
+}
+
+#[test]
+fn test_config_from_tauri_conf() {
+    let config: Config = serde_json::from_value(serde_json::json!({
+        "srcDir": "python",
+        "entryModule": "app.main",
+        "registration": "freezeAfterSetup"
+    }))
+    .unwrap();
+    assert_eq!(config.src_dir, PathBuf::from("python"));
+    assert_eq!(config.entry_module, "app.main");
+    assert_eq!(config.venv, PathBuf::from(".venv"));
+    assert_eq!(config.timeout_secs, DEFAULT_TIMEOUT_SECS);
+    assert_eq!(config.restart_after_secs, 10);
+    assert_eq!(config.registration, RegistrationPolicy::FreezeAfterSetup);
+
+    // Typos are reported instead of silently ignored.
+    assert!(serde_json::from_value::<Config>(serde_json::json!({ "srcdir": "python" })).is_err());
+}
+
+#[tokio::test]
+async fn test_before_main_code_runs_at_init() {
+    let app = mock_app_handle().await;
+    let startup = Startup {
+        dir: std::env::temp_dir(),
+        before_main: vec!["app_mode = 'desktop'".into()],
+        log_level: LogLevel::Off,
+        ..Default::default()
+    };
+    let runner = PyRunner::new();
+    init_python(&app, &runner, &startup).await.unwrap();
+    assert_eq!(
+        runner.read_variable("app_mode").await.unwrap(),
+        serde_json::json!("desktop")
+    );
+}
+
+#[tokio::test]
+async fn test_functions_marked_as_command() {
+    let app = mock_app_handle().await;
+    let runner = app.runner();
+    runner
+        .run(
+            r#"
+@tauri.command
+def double(x):
+    return 2 * x
+
+@tauri.command(name="triple_it", timeout=2.5, windows=["main"])
+def triple(x):
+    return 3 * x
+
+_tauri_plugin_functions = ["my_func"]
+"#,
+        )
+        .await
+        .unwrap();
+    let requests = python_functions(&runner).await.unwrap();
+    let names: Vec<&str> = requests
+        .iter()
+        .map(|request| request.python_function_call.as_str())
+        .collect();
+    assert_eq!(names, ["my_func", "double", "triple_it"]);
+    assert_eq!(requests[2].timeout_ms, Some(2500));
+    assert_eq!(requests[2].windows, Some(vec!["main".to_string()]));
+
+    register_python_functions(&app, requests).await.unwrap();
+    let result = app
+        .call_function(RunRequest {
+            function_name: "triple_it".into(),
+            args: vec![serde_json::json!(2)],
+            ..Default::default()
+        })
+        .await;
+    assert_eq!(result.unwrap().value, "6");
+}
+
+#[cfg(all(feature = "pyo3", not(feature = "rustpython")))]
+#[tokio::test]
+async fn test_typescript_bindings() {
+    let app = mock_app_handle().await;
+    app.run_python(StringRequest {
+        value: r#"
+from typing import Iterator, Optional, TypedDict
+
+class Point(TypedDict):
+    x: float
+    label: Optional[str]
+
+def closest(points: list[Point], limit: int = 1, *, sort: bool) -> Point:
+    """The point closest to the origin."""
+    return points[0]
+
+def count(n: int) -> Iterator[int]:
+    yield from range(n)
+
+def pad(a, b=5, /):
+    return a + b
+
+def collect(a, b=2, *rest):
+    return [a, b, *rest]
+"#
+        .into(),
+    })
+    .await
+    .unwrap();
+    for name in ["closest", "count", "pad", "collect"] {
+        app.register_function(RegisterRequest {
+            python_function_call: name.into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    }
+    let ts = app.typescript_bindings();
+    assert!(
+        ts.contains("export interface Point {\n  x: number;\n  label: string | null;\n}\n"),
+        "{ts}"
+    );
+    assert!(
+        ts.contains(
+            "/**\n * The point closest to the origin.\n */\n\
+             export async function closest(points: Point[], limit: number | undefined, sort: boolean, options?: CallOptions): Promise<Point> {\n  \
+             return callFunctionJson<Point>(\"closest\", [points], { limit, sort }, options);\n}\n"
+        ),
+        "{ts}"
+    );
+    assert!(
+        ts.contains(
+            "export async function count(n: number, onItem: (item: number) => void, options?: CallOptions): Promise<void> {\n  \
+             return callFunctionStream<number>(\"count\", [n], onItem, undefined, options);\n}\n"
+        ),
+        "{ts}"
+    );
+    // Optional positional arguments left out keep their python default.
+    assert!(
+        ts.contains(
+            "export async function pad(a: unknown, b?: unknown, options?: CallOptions): Promise<unknown> {\n  \
+             return callFunctionJson<unknown>(\"pad\", trimArgs([a, b]), undefined, options);\n}\n"
+        ),
+        "{ts}"
+    );
+    // Before `*args`, they are passed positionally, like `*args` itself.
+    assert!(
+        ts.contains(
+            "export async function collect(a: unknown, b?: unknown, rest?: unknown[], options?: CallOptions): Promise<unknown> {\n  \
+             return callFunctionJson<unknown>(\"collect\", trimArgs([a, b, ...(rest ?? [])]), undefined, options);\n}\n"
+        ),
+        "{ts}"
+    );
+    assert!(
+        ts.contains("\nfunction trimArgs(args: unknown[]): unknown[] {\n"),
+        "{ts}"
+    );
+    assert!(!ts.contains("my_func"), "{ts}");
+}
+
+#[tokio::test]
+async fn test_hostile_function_names_are_rejected() {
+    let app = mock_app_handle().await;
+    for name in [
+        "",
+        "my_func()",
+        "my_func) or globals().update(pwned=1) or (my_func",
+        "__import__('os').getcwd",
+        "my_func\npwned = 1",
+        "my_func.",
+        "1st_func",
+    ] {
+        let result = app
+            .register_function(RegisterRequest {
+                python_function_call: name.into(),
+                number_of_args: Some(2),
+                ..Default::default()
+            })
+            .await;
+        assert!(result.is_err(), "{name:?} was registered");
+    }
+    assert!(app.runner().read_variable("pwned").await.is_err());
+    assert!(is_python_name("my_func"));
+    assert!(is_python_name("tauri._connect"));
+    assert!(is_python_name("größe"));
+}
+
+#[tokio::test]
+async fn test_sys_path_with_quotes() {
+    let app = mock_app_handle().await;
+    let dir = std::env::temp_dir().join("tauri \"python\" 'src'\\");
+    let startup = Startup {
+        dir: dir.clone(),
+        log_level: LogLevel::Off,
+        ..Default::default()
+    };
     let runner = PyRunner::new();
-    app.manage(runner);
+    init_python(&app, &runner, &startup).await.unwrap();
+    let sys_path = runner.read_variable("sys.path").await.unwrap();
+    let expected = serde_json::json!(cleanup_path_for_python(&dir));
+    assert!(
+        sys_path.as_array().unwrap().contains(&expected),
+        "{sys_path}"
+    );
+}
+
+#[tokio::test]
+async fn test_calls_fail_after_failed_init() {
+    let app = mock_app_with_state(PluginState {
+        status: tokio::sync::watch::Sender::new(StatusResponse::Failed {
+            error: "NameError: name 'pandsa' is not defined".into(),
+        }),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(
+        serde_json::to_value(app.get_status().await.unwrap()).unwrap(),
+        serde_json::json!({ "status": "failed", "error": "NameError: name 'pandsa' is not defined" })
+    );
+    let err = app
+        .call_function(RunRequest {
+            function_name: "my_func".into(),
+            args: vec![serde_json::json!(1), serde_json::json!(2)],
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Python failed to initialize: NameError"));
+}
+
+// An exception of main.py fails the startup with the traceback in the status.
+#[tokio::test]
+async fn test_failed_main_py_reports_traceback() {
+    let main_py = std::env::temp_dir().join("tauri_plugin_python_test_failing_main.py");
+    std::fs::write(
+        &main_py,
+        "import os\n\ndef load():\n  raise ValueError('no model')\n\nload()\n",
+    )
+    .unwrap();
+    let startup = Startup {
+        dir: std::env::temp_dir(),
+        main_py: Some(main_py.clone()),
+        ..Default::default()
+    };
+    let app = test::mock_app();
+    app.manage(Interpreter::new(PyRunner::new(), startup.clone()));
+    app.manage(PluginState::default());
+    let app = app.handle();
+
+    let result = builder::start_python(app, &startup, Vec::new()).await;
+    let Err(Error::PythonException {
+        type_name, frames, ..
+    }) = &result
+    else {
+        panic!("unexpected result: {result:?}");
+    };
+    assert_eq!(type_name, "ValueError");
+    assert_eq!(frames.last().unwrap().function, "load");
+    assert_eq!(frames.last().unwrap().line, 4);
+
+    builder::started(app, RegistrationPolicy::default(), &result, None);
+    let StatusResponse::Failed { error } = app.get_status().await.unwrap() else {
+        panic!("python should have failed to start");
+    };
+    assert!(error.contains("ValueError: no model"), "{error}");
+    assert!(error.contains("Traceback"), "{error}");
+    assert!(error.contains("raise ValueError('no model')"), "{error}");
+}
+
+// With background init, calls made before python is ready wait for it.
+#[tokio::test]
+async fn test_call_waits_until_ready() {
+    let app = mock_app_with_state(PluginState {
+        ready_timeout: Duration::from_secs(5),
+        ..Default::default()
+    })
+    .await;
+    let state = app.state::<PluginState>();
+    state.status.send_replace(StatusResponse::Starting);
+    app.register_function(RegisterRequest {
+        python_function_call: "my_func".into(),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let ready_later = app.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        ready_later
+            .state::<PluginState>()
+            .status
+            .send_replace(StatusResponse::Ready);
+    });
+    let result = app
+        .call_function(RunRequest {
+            function_name: "my_func".into(),
+            args: vec![serde_json::json!(1), serde_json::json!(2)],
+            ..Default::default()
+        })
+        .await;
+    assert_eq!(result.unwrap().value, "3");
+    assert_eq!(app.wait_ready().await.unwrap(), StatusResponse::Ready);
+}
+
+#[tokio::test]
+async fn test_wait_ready_is_bounded() {
+    let app = mock_app_with_state(PluginState {
+        ready_timeout: Duration::from_millis(100),
+        ..Default::default()
+    })
+    .await;
+    app.state::<PluginState>()
+        .status
+        .send_replace(StatusResponse::Starting);
+    let err = app.wait_ready().await.unwrap_err();
+    assert!(err.to_string().contains("Python is still starting"));
+}
+
+#[tokio::test]
+async fn test_register_after_setup_is_rejected() {
+    let app = mock_app_with_state(PluginState {
+        registration: RegistrationPolicy::FreezeAfterSetup,
+        init_blocked: true.into(),
+        ..Default::default()
+    })
+    .await;
+    let result = app
+        .register_function(RegisterRequest {
+            python_function_call: "my_func".into(),
+            ..Default::default()
+        })
+        .await;
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("Cannot register after setup"));
+}
+
+// A call that leaves the worker stuck gets python restarted: the functions
+// main.py defines again stay callable, python globals start over and the UI is
+// told, also about the functions that are gone.
+#[tokio::test]
+async fn test_restart_after_stuck_call() {
+    let main_py = std::env::temp_dir().join("tauri_plugin_python_test_restart.py");
+    std::fs::write(
+        &main_py,
+        "import time\ndef stuck():\n  time.sleep(30)\ndef ping():\n  return 'pong'\n",
+    )
+    .unwrap();
+    let startup = Startup {
+        dir: std::env::temp_dir(),
+        main_py: Some(main_py.clone()),
+        restart_after: Some(Duration::from_secs(1)),
+        log_level: LogLevel::Off,
+        ..Default::default()
+    };
+    let app = mock_app_with_startup(PluginState::default(), startup).await;
+    let (restarted, mut on_restart) = tokio::sync::mpsc::unbounded_channel();
+    app.listen_any(interpreter::RESTARTED_EVENT, move |event| {
+        let _ = restarted.send(event.payload().to_string());
+    });
+    app.runner().run_file(&main_py).await.unwrap();
+    for name in ["stuck", "ping", "my_func"] {
+        app.register_function(RegisterRequest {
+            python_function_call: name.into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    }
+    let result = app
+        .call_function(RunRequest {
+            function_name: "stuck".into(),
+            timeout_ms: Some(100),
+            ..Default::default()
+        })
+        .await;
+    assert!(matches!(result, Err(Error::Timeout { .. })), "{result:?}");
+    let payload = tokio::time::timeout(Duration::from_secs(20), on_restart.recv())
+        .await
+        .expect("python should have been restarted")
+        .unwrap();
+    let payload: serde_json::Value = serde_json::from_str(&payload).unwrap();
+    assert!(
+        payload["reason"].as_str().unwrap().contains("timed out"),
+        "unexpected payload: {payload}"
+    );
+    // Only defined by the test, not by main.py.
+    assert_eq!(payload["unregistered"], serde_json::json!(["my_func"]));
+
+    // The fresh runner responds right away and is set up like the old one.
+    let response = app
+        .call_function(RunRequest {
+            function_name: "ping".into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.value, "pong");
+    app.run_python(StringRequest {
+        value: "tauri.emit('after-restart')".into(),
+    })
+    .await
+    .unwrap();
+    assert!(app
+        .read_variable(StringRequest {
+            value: "my_var".into(),
+        })
+        .await
+        .is_err());
+    let state = app.state::<PluginState>();
+    assert!(lookup_function(&state, "stuck").is_ok());
+    assert!(matches!(
+        lookup_function(&state, "my_func"),
+        Err(Error::NotRegistered(_))
+    ));
+}
+
+// `@tauri.command` functions of modules main.py imported stay registered after
+// a restart, although these modules aren't imported again.
+#[tokio::test]
+async fn test_restart_keeps_commands_of_imported_modules() {
+    let dir = std::env::temp_dir().join("tauri_plugin_python_test_restart_commands");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("restart_commands_module.py"),
+        "import tauri\n\n@tauri.command\ndef from_module():\n  return 'kept'\n",
+    )
+    .unwrap();
+    let main_py = dir.join("main.py");
+    std::fs::write(
+        &main_py,
+        "import time\nimport tauri\nimport restart_commands_module\n\n@tauri.command\ndef stuck_in_main():\n  time.sleep(30)\n",
+    )
+    .unwrap();
+    let startup = Startup {
+        dir: dir.clone(),
+        main_py: Some(main_py),
+        restart_after: Some(Duration::from_secs(1)),
+        log_level: LogLevel::Off,
+        ..Default::default()
+    };
+    let app = test::mock_app();
+    app.manage(Interpreter::new(PyRunner::new(), startup.clone()));
     app.manage(PluginState::default());
+    let app = app.handle();
+    let result = builder::start_python(app, &startup, Vec::new()).await;
+    builder::started(app, RegistrationPolicy::default(), &result, None);
+    result.unwrap();
+    let (restarted, mut on_restart) = tokio::sync::mpsc::unbounded_channel();
+    app.listen_any(interpreter::RESTARTED_EVENT, move |event| {
+        let _ = restarted.send(event.payload().to_string());
+    });
+
+    let result = app
+        .call_function(RunRequest {
+            function_name: "stuck_in_main".into(),
+            timeout_ms: Some(100),
+            ..Default::default()
+        })
+        .await;
+    assert!(matches!(result, Err(Error::Timeout { .. })), "{result:?}");
+    let payload = tokio::time::timeout(Duration::from_secs(20), on_restart.recv())
+        .await
+        .expect("python should have been restarted")
+        .unwrap();
+    let payload: serde_json::Value = serde_json::from_str(&payload).unwrap();
+    assert_eq!(payload["unregistered"], serde_json::json!([]));
+
+    let response = app
+        .call_function(RunRequest {
+            function_name: "from_module".into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.value, "kept");
+}
+
+// With PyO3, python code of a call that timed out is interrupted, so the
+// worker is free again and python isn't restarted.
+#[cfg(all(feature = "pyo3", not(feature = "rustpython")))]
+#[tokio::test]
+async fn test_timed_out_call_is_interrupted() {
+    let app = mock_app_handle().await;
+    app.runner()
+        .run("spins = 0\ndef spin():\n  global spins\n  spins += 1\n  while True:\n    pass")
+        .await
+        .unwrap();
+    for name in ["spin", "my_func"] {
+        app.register_function(RegisterRequest {
+            python_function_call: name.into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    }
+    let result = app
+        .call_function(RunRequest {
+            function_name: "spin".into(),
+            timeout_ms: Some(200),
+            ..Default::default()
+        })
+        .await;
+    assert!(matches!(result, Err(Error::Timeout { .. })), "{result:?}");
+    let next = app.call_function(RunRequest {
+        function_name: "my_func".into(),
+        args: vec![serde_json::json!(1), serde_json::json!(2)],
+        ..Default::default()
+    });
+    let response = tokio::time::timeout(Duration::from_secs(2), next)
+        .await
+        .expect("the worker should be free again")
+        .unwrap();
+    assert_eq!(response.value, "3");
+    let spins = app
+        .read_variable(StringRequest {
+            value: "spins".into(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(spins.value, "1");
+}
+
+/// A channel that records every message sent through it.
+fn recording_channel() -> (Channel<StreamMessage>, Arc<Mutex<Vec<serde_json::Value>>>) {
+    let messages = Arc::new(Mutex::new(Vec::new()));
+    let sink = messages.clone();
+    let channel = Channel::new(move |body| {
+        if let tauri::ipc::InvokeResponseBody::Json(json) = body {
+            sink.lock()
+                .unwrap()
+                .push(serde_json::from_str(&json).unwrap());
+        }
+        Ok(())
+    });
+    (channel, messages)
+}
+
+// Every value yielded by a generator is sent as its own item, across several
+// batches, followed by a single completion message.
+#[tokio::test]
+async fn test_call_function_stream_generator() {
+    let app = mock_app_handle().await;
+    let runner = app.runner();
+    runner
+        .run("def count(n):\n  for i in range(n):\n    yield {'i': i}")
+        .await
+        .unwrap();
+    app.register_function(RegisterRequest {
+        python_function_call: "count".into(),
+        number_of_args: None,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let (channel, messages) = recording_channel();
+    let total = STREAM_BATCH_SIZE + 3;
+    app.call_function_stream(
+        RunRequest {
+            function_name: "count".into(),
+            args: vec![serde_json::json!(total)],
+            ..Default::default()
+        },
+        channel,
+    )
+    .await
+    .unwrap();
+    let messages = messages.lock().unwrap();
+    assert_eq!(messages.len(), total + 1);
+    for (i, message) in messages[..total].iter().enumerate() {
+        assert_eq!(
+            message,
+            &serde_json::json!({"event": "item", "data": {"i": i}})
+        );
+    }
+    assert_eq!(messages[total], serde_json::json!({"event": "done"}));
+}
+
+// Async generators are advanced on the event loop the same way.
+#[tokio::test]
+async fn test_call_function_stream_async_generator() {
+    let app = mock_app_handle().await;
+    let runner = app.runner();
+    runner
+        .run("import asyncio\nasync def ticks(n):\n  for i in range(n):\n    await asyncio.sleep(0.001)\n    yield i")
+        .await
+        .unwrap();
+    app.register_function(RegisterRequest {
+        python_function_call: "ticks".into(),
+        number_of_args: None,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let (channel, messages) = recording_channel();
+    app.call_function_stream(
+        RunRequest {
+            function_name: "ticks".into(),
+            args: vec![serde_json::json!(3)],
+            ..Default::default()
+        },
+        channel,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        *messages.lock().unwrap(),
+        vec![
+            serde_json::json!({"event": "item", "data": 0}),
+            serde_json::json!({"event": "item", "data": 1}),
+            serde_json::json!({"event": "item", "data": 2}),
+            serde_json::json!({"event": "done"}),
+        ]
+    );
+}
+
+// Items yielded before an exception are still delivered, then the error ends
+// the stream and is returned to the caller.
+#[tokio::test]
+async fn test_call_function_stream_error_after_items() {
+    let app = mock_app_handle().await;
+    let runner = app.runner();
+    runner
+        .run("def fails_late():\n  yield 1\n  raise ValueError('late boom')")
+        .await
+        .unwrap();
+    app.register_function(RegisterRequest {
+        python_function_call: "fails_late".into(),
+        number_of_args: None,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let (channel, messages) = recording_channel();
+    let err = app
+        .call_function_stream(
+            RunRequest {
+                function_name: "fails_late".into(),
+                ..Default::default()
+            },
+            channel,
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("late boom"), "unexpected error: {err}");
+    let messages = messages.lock().unwrap();
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0], serde_json::json!({"event": "item", "data": 1}));
+    assert_eq!(messages[1]["event"], "error");
+}
+
+// Streams of the frontend only pull the next batch from the generator once
+// the previous one was acknowledged.
+#[tokio::test]
+async fn test_stream_waits_for_acknowledgement() {
+    let app = mock_app_handle().await;
+    app.runner()
+        .run("pulled = 0\ndef counted(n):\n  global pulled\n  for i in range(n):\n    pulled += 1\n    yield i")
+        .await
+        .unwrap();
+    app.register_function(RegisterRequest {
+        python_function_call: "counted".into(),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let (channel, messages) = recording_channel();
+    let channel_id = channel.id();
+    let total = 2 * STREAM_BATCH_SIZE + 1;
+    let streaming = tokio::spawn({
+        let app = app.clone();
+        async move {
+            let payload = RunRequest {
+                function_name: "counted".into(),
+                args: vec![serde_json::json!(total)],
+                ..Default::default()
+            };
+            stream_function(&app, payload, channel, true).await
+        }
+    });
+    let pulled = || async {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let pulled = app.read_variable(StringRequest {
+            value: "pulled".into(),
+        });
+        pulled.await.unwrap().value
+    };
+    let acks = &app.state::<PluginState>().inner().streams;
+
+    assert_eq!(pulled().await, STREAM_BATCH_SIZE.to_string());
+    assert_eq!(messages.lock().unwrap().len(), STREAM_BATCH_SIZE + 1);
+    assert_eq!(
+        messages.lock().unwrap().last().unwrap(),
+        &serde_json::json!({"event": "batch", "data": 1})
+    );
+    assert!(acks.ack(channel_id, 1));
+    assert_eq!(pulled().await, (2 * STREAM_BATCH_SIZE).to_string());
+    assert!(acks.ack(channel_id, 2));
+    streaming.await.unwrap().unwrap();
+
+    let messages = messages.lock().unwrap();
+    assert_eq!(messages.len(), total + 3);
+    assert_eq!(messages[total + 2], serde_json::json!({"event": "done"}));
+    assert!(!acks.ack(channel_id, 3));
+}
 
-    let runner = app.state::<PyRunner>().inner();
+// A timeout cancels the batch an async generator is still collecting and
+// closes the generator, without leaving its task behind.
+#[tokio::test]
+async fn test_stream_timeout_closes_async_generator() {
+    let app = mock_app_handle().await;
+    let runner = app.runner();
     runner
-        .run("my_var = 123\ndef my_func(a, b):\n  return a + b")
+        .run(
+            r#"
+import asyncio
+closed = False
+async def slow_ticks():
+    global closed
+    try:
+        yield 0
+        while True:
+            await asyncio.sleep(10)
+            yield 1
+    finally:
+        closed = True
+"#,
+        )
         .await
         .unwrap();
+    app.register_function(RegisterRequest {
+        python_function_call: "slow_ticks".into(),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let (channel, _messages) = recording_channel();
+    let result = app
+        .call_function_stream(
+            RunRequest {
+                function_name: "slow_ticks".into(),
+                timeout_ms: Some(200),
+                ..Default::default()
+            },
+            channel,
+        )
+        .await;
+    assert!(matches!(result, Err(Error::Timeout { .. })), "{result:?}");
 
-    app.handle().clone()
+    let mut closed = String::new();
+    for _ in 0..20 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        closed = app
+            .read_variable(StringRequest {
+                value: "closed".into(),
+            })
+            .await
+            .unwrap()
+            .value;
+        if closed == "true" {
+            break;
+        }
+    }
+    assert_eq!(closed, "true");
+    let tasks = runner
+        .eval("len(_tauri_plugin_tasks) + len(_tauri_plugin_stream_tasks)")
+        .await
+        .unwrap();
+    assert_eq!(tasks, serde_json::json!(0));
 }
 
+// Generators can't be called through call_function, which would only return
+// the stringified generator object.
 #[tokio::test]
-async fn test_read_variable() {
+async fn test_call_function_rejects_generator() {
     let app = mock_app_handle().await;
-    let payload = StringRequest {
-        value: "my_var".into(),
-    };
-    let response = app.read_variable(payload).await.unwrap();
-    assert_eq!(response.value, "123");
+    let runner = app.runner();
+    runner.run("def gen():\n  yield 1").await.unwrap();
+    app.register_function(RegisterRequest {
+        python_function_call: "gen".into(),
+        number_of_args: None,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let err = app
+        .call_function(RunRequest {
+            function_name: "gen".into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("use call_function_stream"),
+        "unexpected error: {err}"
+    );
 }
 
+// Events emitted through the built-in `tauri` module reach Rust listeners by
+// the time the call returns, in order. The tests use the `tauri` global rather
+// than `import tauri`, as `sys.modules` is shared by all test apps.
 #[tokio::test]
-async fn test_run_python() {
+async fn test_python_emits_events() {
     let app = mock_app_handle().await;
-    let payload = StringRequest {
-        value: "new_var = 456".into(),
-    };
-    app.run_python(payload).await.unwrap();
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    app.listen_any("progress", move |event| {
+        sink.lock().unwrap().push(event.payload().to_string());
+    });
+    let runner = app.runner();
+    runner
+        .run("def work(n):\n  for i in range(n):\n    tauri.emit('progress', {'step': i})\n  tauri.emit_to('main', 'progress', 'done')\n  return n")
+        .await
+        .unwrap();
+    app.register_function(RegisterRequest {
+        python_function_call: "work".into(),
+        number_of_args: None,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    app.call_function(RunRequest {
+        function_name: "work".into(),
+        args: vec![serde_json::json!(2)],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    assert_eq!(
+        *received.lock().unwrap(),
+        vec![r#"{"step":0}"#, r#"{"step":1}"#, r#""done""#]
+    );
+}
 
-    // Verify the code was run by reading the variable back
-    let read_payload = StringRequest {
-        value: "new_var".into(),
-    };
-    let response = app.read_variable(read_payload).await.unwrap();
-    assert_eq!(response.value, "456");
+// A payload that can't be sent as JSON fails inside python, where the caller
+// can handle it, instead of being dropped silently.
+#[tokio::test]
+async fn test_python_emit_rejects_unserializable_payload() {
+    let app = mock_app_handle().await;
+    let err = app
+        .run_python(StringRequest {
+            value: "tauri.emit('progress', object())".into(),
+        })
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("not JSON serializable"),
+        "unexpected error: {err}"
+    );
 }
 
+// Connecting a new runner, as on a restart, removes the bridge handler of the
+// previous one, which would otherwise keep the app alive forever.
+#[cfg(all(feature = "pyo3", not(feature = "rustpython")))]
 #[tokio::test]
-async fn test_register_and_call_function() {
+async fn test_reconnect_removes_previous_bridge() {
     let app = mock_app_handle().await;
+    let runner = app.runner();
+    let previous = runner.eval("tauri._bridge_id").await.unwrap();
+    init_tauri_module(&app, &runner).await.unwrap();
+    runner.run("tauri.emit('reconnected')").await.unwrap();
 
-    // 1. Register the function
-    let register_payload = RegisterRequest {
-        python_function_call: "my_func".into(),
-        number_of_args: Some(2),
-    };
-    app.register_function(register_payload).await.unwrap();
+    runner
+        .call_function("tauri._connect", vec![previous])
+        .await
+        .unwrap();
+    let err = runner.run("tauri.emit('disconnected')").await.unwrap_err();
+    assert!(err.to_string().contains("not connected"), "{err}");
+}
 
-    // 2. Call the registered function
-    let call_payload = RunRequest {
-        function_name: "my_func".into(),
-        args: vec![serde_json::json!(10), serde_json::json!(20)],
-    };
-    let response = app.call_function(call_payload).await.unwrap();
-    assert_eq!(response.value, "30");
+#[cfg(all(feature = "pyo3", not(feature = "rustpython")))]
+fn rust_handler(
+    handler: impl Fn(Vec<serde_json::Value>) -> std::result::Result<serde_json::Value, String>
+        + Send
+        + Sync
+        + 'static,
+) -> Arc<RustHandler> {
+    Arc::new(handler)
+}
+
+// A function executed via call_function can call back into Rust, and errors of
+// the Rust handler surface as `tauri.RustError`. Needs the native bridge.
+#[cfg(all(feature = "pyo3", not(feature = "rustpython")))]
+#[tokio::test]
+async fn test_python_calls_rust_handler() {
+    let mut rust_handlers = HashMap::new();
+    rust_handlers.insert(
+        "add".to_string(),
+        rust_handler(|args| Ok(args.iter().filter_map(|v| v.as_i64()).sum::<i64>().into())),
+    );
+    rust_handlers.insert(
+        "fail".to_string(),
+        rust_handler(|_| Err("rust says no".into())),
+    );
+    let app = mock_app_with_state(PluginState {
+        rust_handlers,
+        ..Default::default()
+    })
+    .await;
+    let runner = app.runner();
+    runner
+        .run("def via_rust(a, b):\n  return tauri.call_rust('add', a, b) * 2\ndef rust_fails(name):\n  try:\n    tauri.call_rust(name)\n  except tauri.RustError as e:\n    return str(e)")
+        .await
+        .unwrap();
+    for name in ["via_rust", "rust_fails"] {
+        app.register_function(RegisterRequest {
+            python_function_call: name.into(),
+            number_of_args: None,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    }
+    let doubled = app
+        .call_function_json(RunRequest {
+            function_name: "via_rust".into(),
+            args: vec![serde_json::json!(1), serde_json::json!(2)],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(doubled.value, serde_json::json!(6));
+    for (name, expected) in [
+        ("fail", "rust says no"),
+        ("missing", "No Rust handler registered as 'missing'"),
+    ] {
+        let message = app
+            .call_function_json(RunRequest {
+                function_name: "rust_fails".into(),
+                args: vec![serde_json::json!(name)],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(message.value, serde_json::json!(expected));
+    }
+}
+
+#[cfg(feature = "rustpython")]
+#[tokio::test]
+async fn test_rustpython_call_rust_not_supported() {
+    let app = mock_app_handle().await;
+    let err = app
+        .run_python(StringRequest {
+            value: "tauri.call_rust('add', 1)".into(),
+        })
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("requires the PyO3 backend"),
+        "unexpected error: {err}"
+    );
 }
 
 #[tokio::test]
@@ -78,6 +1799,7 @@ async fn test_call_unregistered_function_fails() {
     let call_payload = RunRequest {
         function_name: "unregistered_func".into(),
         args: vec![],
+        ..Default::default()
     };
     let result = app.call_function(call_payload).await;
     assert!(result.is_err());
@@ -85,6 +1807,141 @@ async fn test_call_unregistered_function_fails() {
     assert!(err_msg.contains("Function unregistered_func has not been registered yet"));
 }
 
+/// Registers `name` and calls it with `(1, 2)`.
+async fn register_and_call(
+    app: &AppHandle<MockRuntime>,
+    name: &str,
+) -> crate::Result<StringResponse> {
+    app.register_function(RegisterRequest {
+        python_function_call: name.into(),
+        ..Default::default()
+    })
+    .await?;
+    app.call_function(RunRequest {
+        function_name: name.into(),
+        args: vec![serde_json::json!(1), serde_json::json!(2)],
+        ..Default::default()
+    })
+    .await
+}
+
+// A call that closes the registration while python still describes a function
+// to register rejects the registration.
+#[tokio::test]
+async fn test_registration_closed_while_describing() {
+    let app = mock_app_handle().await;
+    let busy = app.runner();
+    let busy = tokio::spawn(async move { busy.run("import time\ntime.sleep(0.3)").await });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let register = {
+        let app = app.clone();
+        tokio::spawn(async move {
+            app.register_function(RegisterRequest {
+                python_function_call: "my_func".into(),
+                ..Default::default()
+            })
+            .await
+        })
+    };
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let state = app.state::<PluginState>();
+    assert!(lookup_function(&state, "other").is_err());
+    let result = register.await.unwrap();
+    busy.await.unwrap().unwrap();
+    assert!(
+        matches!(result, Err(Error::RegistrationClosed { .. })),
+        "{result:?}"
+    );
+    assert!(matches!(
+        lookup_function(&state, "my_func"),
+        Err(Error::NotRegistered(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_registration_policy_never() {
+    let app = mock_app_with_state(PluginState {
+        registration: RegistrationPolicy::Never,
+        ..Default::default()
+    })
+    .await;
+    app.run_python(StringRequest {
+        value: "def other_func(a, b):\n  return a * b".into(),
+    })
+    .await
+    .unwrap();
+    assert_eq!(register_and_call(&app, "my_func").await.unwrap().value, "3");
+    // Still open after a call, also for unregistering.
+    assert_eq!(
+        register_and_call(&app, "other_func").await.unwrap().value,
+        "2"
+    );
+    app.unregister_function(StringRequest {
+        value: "my_func".into(),
+    })
+    .await
+    .unwrap();
+    let err = app
+        .call_function(RunRequest {
+            function_name: "my_func".into(),
+            args: vec![serde_json::json!(1), serde_json::json!(2)],
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), "notRegistered");
+    let err = app
+        .unregister_function(StringRequest {
+            value: "my_func".into(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), "notRegistered");
+}
+
+#[tokio::test]
+async fn test_registration_policy_rust_only() {
+    let app = mock_app_with_state(PluginState {
+        registration: RegistrationPolicy::RustOnly,
+        ..Default::default()
+    })
+    .await;
+    let request = || RegisterRequest {
+        python_function_call: "my_func".into(),
+        ..Default::default()
+    };
+    let err = commands::register_function(app.clone(), request())
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Cannot register from the frontend");
+    assert_eq!(register_and_call(&app, "my_func").await.unwrap().value, "3");
+    app.register_function(request()).await.unwrap();
+    let payload = || StringRequest {
+        value: "my_func".into(),
+    };
+    let err = commands::unregister_function(app.clone(), payload())
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), "registrationClosed");
+    assert_eq!(err.to_string(), "Cannot unregister from the frontend");
+    app.unregister_function(payload()).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_unregister_after_call_fails() {
+    let app = mock_app_handle().await;
+    register_and_call(&app, "my_func").await.unwrap();
+    let result = app
+        .unregister_function(StringRequest {
+            value: "my_func".into(),
+        })
+        .await;
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("Cannot unregister after function called"));
+}
+
 #[tokio::test]
 async fn test_register_after_call_fails() {
     let app = mock_app_handle().await;
@@ -93,11 +1950,13 @@ async fn test_register_after_call_fails() {
     let register_payload = RegisterRequest {
         python_function_call: "my_func".into(),
         number_of_args: Some(2),
+        ..Default::default()
     };
     app.register_function(register_payload).await.unwrap();
     let call_payload = RunRequest {
         function_name: "my_func".into(),
         args: vec![serde_json::json!(1), serde_json::json!(2)],
+        ..Default::default()
     };
     app.call_function(call_payload).await.unwrap();
 
@@ -105,6 +1964,7 @@ async fn test_register_after_call_fails() {
     let second_register_payload = RegisterRequest {
         python_function_call: "my_var".into(), // can be anything
         number_of_args: None,
+        ..Default::default()
     };
     let result = app.register_function(second_register_payload).await;
     assert!(result.is_err());
@@ -121,6 +1981,7 @@ async fn test_registering_in_second_app_after_first_call_is_allowed() {
         .register_function(RegisterRequest {
             python_function_call: "my_func".into(),
             number_of_args: Some(2),
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -128,6 +1989,7 @@ async fn test_registering_in_second_app_after_first_call_is_allowed() {
         .call_function(RunRequest {
             function_name: "my_func".into(),
             args: vec![serde_json::json!(1), serde_json::json!(2)],
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -137,6 +1999,7 @@ async fn test_registering_in_second_app_after_first_call_is_allowed() {
         .register_function(RegisterRequest {
             python_function_call: "my_func".into(),
             number_of_args: Some(2),
+            ..Default::default()
         })
         .await;
 
@@ -157,6 +2020,7 @@ async fn test_register_function_arg_mismatch_returns_error_instead_of_panicking(
         app.register_function(RegisterRequest {
             python_function_call: "my_func".into(),
             number_of_args: Some(1),
+            ..Default::default()
         })
         .await
     });
@@ -187,6 +2051,7 @@ async fn test_rustpython_register_skips_signature_check() {
     app.register_function(RegisterRequest {
         python_function_call: "my_func".into(),
         number_of_args: Some(1),
+        ..Default::default()
     })
     .await
     .expect("registration should succeed when the signature check can't run");
@@ -196,6 +2061,7 @@ async fn test_rustpython_register_skips_signature_check() {
         .call_function(RunRequest {
             function_name: "my_func".into(),
             args: vec![serde_json::json!(10), serde_json::json!(20)],
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -213,6 +2079,7 @@ async fn test_failed_registration_does_not_allow_call() {
         .register_function(RegisterRequest {
             python_function_call: "does_not_exist".into(),
             number_of_args: None,
+            ..Default::default()
         })
         .await;
     assert!(register_result.is_err());
@@ -221,6 +2088,7 @@ async fn test_failed_registration_does_not_allow_call() {
         .call_function(RunRequest {
             function_name: "does_not_exist".into(),
             args: vec![],
+            ..Default::default()
         })
         .await;
     assert!(
@@ -240,6 +2108,7 @@ async fn test_register_nonexistent_function_fails() {
         .register_function(RegisterRequest {
             python_function_call: "does_not_exist".into(),
             number_of_args: None,
+            ..Default::default()
         })
         .await;
     assert!(result.is_err());
@@ -252,7 +2121,7 @@ async fn test_register_nonexistent_function_fails() {
 #[tokio::test]
 async fn test_stdio_guard_keeps_print_working() {
     let app = mock_app_handle().await;
-    let runner = app.state::<PyRunner>().inner();
+    let runner = app.runner();
     runner
         .run(PY_STDIO_GUARD)
         .await
@@ -269,7 +2138,7 @@ async fn test_stdio_guard_keeps_print_working() {
 #[tokio::test]
 async fn test_registered_function_can_print_and_return() {
     let app = mock_app_handle().await;
-    let runner = app.state::<PyRunner>().inner();
+    let runner = app.runner();
     runner.run(PY_STDIO_GUARD).await.unwrap();
     runner
         .run("def printer(x):\n  print('side effect', x)\n  return x * 2")
@@ -278,6 +2147,7 @@ async fn test_registered_function_can_print_and_return() {
     app.register_function(RegisterRequest {
         python_function_call: "printer".into(),
         number_of_args: None,
+        ..Default::default()
     })
     .await
     .unwrap();
@@ -285,6 +2155,7 @@ async fn test_registered_function_can_print_and_return() {
         .call_function(RunRequest {
             function_name: "printer".into(),
             args: vec![serde_json::json!(21)],
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -296,7 +2167,7 @@ async fn test_registered_function_can_print_and_return() {
 #[tokio::test]
 async fn test_read_variable_types() {
     let app = mock_app_handle().await;
-    let runner = app.state::<PyRunner>().inner();
+    let runner = app.runner();
     runner
         .run("v_int=7\nv_float=1.5\nv_bool=True\nv_list=[1,2,3]\nv_str='hi'")
         .await
@@ -322,7 +2193,7 @@ async fn test_read_variable_types() {
 #[tokio::test]
 async fn test_unicode_round_trip() {
     let app = mock_app_handle().await;
-    let runner = app.state::<PyRunner>().inner();
+    let runner = app.runner();
     runner.run("v_uni = 'héllo🐍'").await.unwrap();
     let resp = app
         .read_variable(StringRequest {
@@ -337,7 +2208,7 @@ async fn test_unicode_round_trip() {
 #[tokio::test]
 async fn test_call_function_with_list_arg() {
     let app = mock_app_handle().await;
-    let runner = app.state::<PyRunner>().inner();
+    let runner = app.runner();
     runner
         .run("def join_list(items):\n  return ','.join(str(x) for x in items)")
         .await
@@ -345,6 +2216,7 @@ async fn test_call_function_with_list_arg() {
     app.register_function(RegisterRequest {
         python_function_call: "join_list".into(),
         number_of_args: Some(1),
+        ..Default::default()
     })
     .await
     .unwrap();
@@ -352,6 +2224,7 @@ async fn test_call_function_with_list_arg() {
         .call_function(RunRequest {
             function_name: "join_list".into(),
             args: vec![serde_json::json!([1, 2, 3])],
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -387,7 +2260,7 @@ async fn test_state_persists_between_calls() {
 #[tokio::test]
 async fn test_error_message_propagates_from_called_function() {
     let app = mock_app_handle().await;
-    let runner = app.state::<PyRunner>().inner();
+    let runner = app.runner();
     runner
         .run("def raiser(x):\n  raise ValueError('custom boom ' + str(x))")
         .await
@@ -395,6 +2268,7 @@ async fn test_error_message_propagates_from_called_function() {
     app.register_function(RegisterRequest {
         python_function_call: "raiser".into(),
         number_of_args: None,
+        ..Default::default()
     })
     .await
     .unwrap();
@@ -402,6 +2276,7 @@ async fn test_error_message_propagates_from_called_function() {
         .call_function(RunRequest {
             function_name: "raiser".into(),
             args: vec![serde_json::json!(42)],
+            ..Default::default()
         })
         .await
         .unwrap_err()
@@ -416,6 +2291,251 @@ async fn test_error_message_propagates_from_called_function() {
     );
 }
 
+#[cfg(all(feature = "pyo3", not(feature = "rustpython")))]
+#[tokio::test]
+async fn test_python_exception_keeps_details() {
+    let app = mock_app_handle().await;
+    app.runner()
+        .run("def check(x):\n  if x < 0:\n    raise ValueError('negative: ' + str(x))\n  return x")
+        .await
+        .unwrap();
+    app.register_function(RegisterRequest {
+        python_function_call: "check".into(),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let err = app
+        .call_function(RunRequest {
+            function_name: "check".into(),
+            args: vec![serde_json::json!(-1)],
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), "pythonException");
+    let Error::PythonException {
+        type_name,
+        message,
+        traceback,
+        frames,
+        ..
+    } = &err
+    else {
+        panic!("unexpected error: {err:?}");
+    };
+    assert_eq!(type_name, "ValueError");
+    assert_eq!(message, "negative: -1");
+    assert!(traceback.contains("raise ValueError"), "{traceback}");
+    assert_eq!(frames.last().unwrap().function, "check");
+    assert_eq!(frames.last().unwrap().line, 3);
+
+    let json = serde_json::to_value(&err).unwrap();
+    assert_eq!(json["kind"], "pythonException");
+    assert_eq!(json["typeName"], "ValueError");
+    assert_eq!(json["exceptionMessage"], "negative: -1");
+    assert_eq!(
+        json["message"],
+        "Error calling Python function 'check': ValueError: negative: -1"
+    );
+}
+
+#[test]
+fn test_error_serializes_with_kind() {
+    let json = serde_json::to_value(Error::Timeout {
+        function: "fit".into(),
+        timeout_ms: 1000,
+    })
+    .unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "kind": "timeout",
+            "message": "Function 'fit' timed out after 1000 ms",
+            "function": "fit",
+            "timeoutMs": 1000
+        })
+    );
+    let json = serde_json::to_value(Error::NotRegistered("f".into())).unwrap();
+    assert_eq!(json["kind"], "notRegistered");
+    assert_eq!(json["message"], "Function f has not been registered yet");
+}
+
+#[tokio::test]
+async fn test_python_output_is_kept() {
+    let app = mock_app_with_state(PluginState {
+        output: output::Output::new(2, false),
+        ..Default::default()
+    })
+    .await;
+    let runner = app.runner();
+    runner.run(PY_STDIO_GUARD).await.unwrap();
+    // Streams of its own, as sys.stdout is shared with the other tests.
+    runner
+        .run("out = _TauriStream('stdout', None)\nout.write('one\\ntwo\\nthr')\nout.write('ee\\n')\n_TauriStream('stderr', None).write('oops\\n')")
+        .await
+        .unwrap();
+    bridge::drain_outbox(&app, &runner).await;
+    let lines = app.get_output().await.unwrap();
+    assert_eq!(
+        lines,
+        vec![
+            OutputLine {
+                stream: OutputStream::Stdout,
+                line: "three".into()
+            },
+            OutputLine {
+                stream: OutputStream::Stderr,
+                line: "oops".into()
+            },
+        ]
+    );
+}
+
+/// Records of the `log` crate, kept by [`test_logger`].
+type LoggedRecords = Mutex<Vec<(log::Level, String, String, Option<u32>)>>;
+
+/// Installs a logger for the whole test binary and returns its records.
+fn test_logger() -> &'static LoggedRecords {
+    struct TestLogger(LoggedRecords);
+    impl log::Log for TestLogger {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+        fn log(&self, record: &log::Record) {
+            self.0.lock().unwrap().push((
+                record.level(),
+                record.target().to_string(),
+                record.args().to_string(),
+                record.line(),
+            ));
+        }
+        fn flush(&self) {}
+    }
+    static LOGGER: std::sync::OnceLock<TestLogger> = std::sync::OnceLock::new();
+    let logger = LOGGER.get_or_init(|| TestLogger(Mutex::new(Vec::new())));
+    if log::set_logger(logger).is_ok() {
+        log::set_max_level(log::LevelFilter::Trace);
+    }
+    &logger.0
+}
+
+/// Held by tests that change python's root logger, which all PyO3 runners share.
+#[cfg(all(feature = "pyo3", not(feature = "rustpython")))]
+static ROOT_LOGGER: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+#[cfg(all(feature = "pyo3", not(feature = "rustpython")))]
+#[tokio::test]
+async fn test_python_logging_reaches_log_crate() {
+    let _root_logger = ROOT_LOGGER.lock().await;
+    let records = test_logger();
+    let app = mock_app_handle().await;
+    let runner = app.runner();
+    runner
+        .run(
+            r#"
+tauri._install_log_handler(20)
+import logging
+import threading
+_log = logging.getLogger("test_log_bridge")
+_log.debug("dropped")
+_log.info("from main")
+_worker = threading.Thread(target=lambda: _log.error("from thread"))
+_worker.start()
+_worker.join()
+"#,
+        )
+        .await
+        .unwrap();
+    bridge::drain_outbox(&app, &runner).await;
+    let logged: Vec<_> = records
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, target, _, _)| target == "python::test_log_bridge")
+        .map(|(level, _, message, line)| (*level, message.clone(), line.is_some()))
+        .collect();
+    assert_eq!(
+        logged,
+        vec![
+            (log::Level::Info, "from main".to_string(), true),
+            (log::Level::Error, "from thread".to_string(), true),
+        ]
+    );
+}
+
+// The log handler is added after main.py ran, so `logging.basicConfig()` there
+// still configures logging, and the root level it sets is kept.
+#[cfg(all(feature = "pyo3", not(feature = "rustpython")))]
+#[tokio::test]
+async fn test_basic_config_in_main_py() {
+    let _root_logger = ROOT_LOGGER.lock().await;
+    let records = test_logger();
+    let main_py = std::env::temp_dir().join("tauri_plugin_python_test_basic_config.py");
+    std::fs::write(
+        &main_py,
+        r#"import io
+import logging
+
+# Like in a fresh interpreter, whose root logger has no handlers yet.
+logging.getLogger().handlers.clear()
+stream = io.StringIO()
+logging.basicConfig(level=logging.INFO, stream=stream, format="%(message)s")
+"#,
+    )
+    .unwrap();
+    let startup = Startup {
+        dir: std::env::temp_dir(),
+        main_py: Some(main_py.clone()),
+        log_level: LogLevel::Debug,
+        ..Default::default()
+    };
+    let app = test::mock_app();
+    app.manage(Interpreter::new(PyRunner::new(), startup.clone()));
+    app.manage(PluginState::default());
+    let app = app.handle();
+    builder::start_python(app, &startup, Vec::new())
+        .await
+        .unwrap();
+
+    let runner = app.runner();
+    runner
+        .run(
+            r#"
+_log = logging.getLogger("test_basic_config")
+_log.debug("dropped")
+_log.info("configured")
+"#,
+        )
+        .await
+        .unwrap();
+    let handlers = runner
+        .eval("sorted(type(h).__name__ for h in logging.getLogger().handlers)")
+        .await
+        .unwrap();
+    let level = runner.eval("logging.getLogger().level").await.unwrap();
+    let printed = runner.eval("stream.getvalue()").await.unwrap();
+    runner
+        .run("logging.getLogger().handlers.clear()\nlogging.getLogger().setLevel(logging.WARNING)")
+        .await
+        .unwrap();
+
+    assert_eq!(
+        handlers,
+        serde_json::json!(["StreamHandler", "_TauriLogHandler"])
+    );
+    assert_eq!(level, serde_json::json!(20));
+    assert_eq!(printed, serde_json::json!("configured\n"));
+    let logged: Vec<_> = records
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, target, _, _)| target == "python::test_basic_config")
+        .map(|(level, _, message, _)| (*level, message.clone()))
+        .collect();
+    assert_eq!(logged, vec![(log::Level::Info, "configured".to_string())]);
+}
+
 // Only the RustPython backend includes a traceback (with line numbers) in the
 // error string; PyO3 surfaces just "ExceptionType: message". This pins down that
 // running PY_STDIO_GUARD as a *separate* unit beforehand does not shift the line
@@ -424,7 +2544,7 @@ async fn test_error_message_propagates_from_called_function() {
 #[tokio::test]
 async fn test_error_line_number_not_shifted_by_stdio_guard() {
     let app = mock_app_handle().await;
-    let runner = app.state::<PyRunner>().inner();
+    let runner = app.runner();
     runner.run(PY_STDIO_GUARD).await.unwrap();
     let code = "x = 1\ny = 2\nraise ValueError('boom')\n";
     let err = runner.run(code).await.unwrap_err().to_string();